@@ -0,0 +1,227 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! A PAM module checking the quality of new passwords, as a replacement of `pam_pwquality.so`.
+//!
+//! The module only provides the `password` management group. It is configured by
+//! `/etc/security/pwquality.conf` and by its arguments, which are the same as the ones of
+//! pam_pwquality (e.g. `retry=3`, `minlen=12`, `enforce_for_root`, `local_users_only`), plus:
+//!
+//!  - `use_authtok` to use the password set by a previous module instead of prompting,
+//!  - `debug` to log the rejected passwords reasons to syslog,
+//!  - `breach_db=<path>` and `breach_db_ntlm=<path>` to reject passwords found in a
+//!    SHA-1 or NTLM breached passwords list,
+//!  - `breach_threshold=<count>` to only reject passwords found at least that many times,
+//!  - `max_keyboard_walk=<length>` to reject passwords with long keyboard walks,
+//!  - `bad_words_file=<path>` to reject passwords containing a word or phrase of a file,
+//!    one per line,
+//!  - `min_unique=<count>` to reject passwords with less than `count` different characters.
+//!
+//! Site-specific rules are added in the `rules` module.
+//!
+//! The module can be tried without a real login with [pam_wrapper](https://cwrap.org/pam_wrapper.html)
+//! and the test service in `pam.d/pwquality-test`, for instance with `pamtester`:
+//!
+//! ```sh
+//! cargo build -p pam_pwquality_rs
+//! mkdir -p /tmp/pam.d && sed "s|@MODULE@|$PWD/target/debug/libpam_pwquality_rs.so|" pam-pwquality/pam.d/pwquality-test > /tmp/pam.d/pwquality-test
+//! LD_PRELOAD=libpam_wrapper.so PAM_WRAPPER=1 PAM_WRAPPER_SERVICE_DIR=/tmp/pam.d pamtester pwquality-test $USER chauthtok
+//! ```
+//!
+//! The same service is used by the tests in `tests/chauthtok.rs`, which change the password of
+//! the current user through Linux-PAM and are run with `cargo test -p pam_pwquality_rs -- --ignored`.
+
+extern crate libc;
+extern crate pwquality;
+extern crate pwquality_sys;
+
+mod pam;
+mod rules;
+
+use pam::*;
+
+use pwquality::PWQuality;
+use pwquality::breach::{BreachDatabase, HashKind};
+use pwquality::enforcement::Bypass;
+
+use pwquality_sys::Error;
+
+use libc::{c_char, c_int};
+
+use std::env;
+use std::ffi::CStr;
+use std::slice;
+
+// the options of the module not handled by the library
+#[derive(Debug, Default)]
+struct Options {
+    debug: bool,
+    min_unique: Option<usize>,
+}
+
+fn configure(pamh: &Handle, pwq: &PWQuality, args: &[String]) -> Options {
+    let mut options = Options::default();
+    for arg in args {
+        let (key, value) = match arg.find('=') {
+            Some(idx) => (&arg[..idx], Some(&arg[idx + 1..])),
+            None => (arg.as_str(), None),
+        };
+        let res = match (key, value) {
+            ("debug", None) => {
+                options.debug = true;
+                Ok(())
+            },
+            // handled by `pam_get_authtok`
+            ("use_authtok", None) | ("authtok_type", Some(_)) => Ok(()),
+            ("breach_db", Some(path)) | ("breach_db_ntlm", Some(path)) => {
+                let kind = if key == "breach_db" { HashKind::Sha1 } else { HashKind::Ntlm };
+                BreachDatabase::open(path, kind)
+                    .map(|database| pwq.set_breach_database(Some(database)))
+                    .map_err(|e| format!("cannot open breach database {}: {}", path, e))
+            },
+            ("breach_threshold", Some(count)) => count.parse()
+                .map(|count| pwq.set_breach_threshold(count))
+                .map_err(|_| format!("invalid breach threshold {}", count)),
+            ("max_keyboard_walk", Some(length)) => length.parse()
+                .map(|length| pwq.set_max_keyboard_walk(length))
+                .map_err(|_| format!("invalid keyboard walk length {}", length)),
+            ("bad_words_file", Some(path)) => pwq.load_bad_words_file(path)
+                .map_err(|e| format!("cannot read bad words file {}: {}", path, e.message("en"))),
+            ("min_unique", Some(count)) => count.parse()
+                .map(|count| options.min_unique = Some(count))
+                .map_err(|_| format!("invalid number of different characters {}", count)),
+            _ => pwq.set_option(arg).map_err(|_| format!("unknown or broken option {}", arg)),
+        };
+        if let Err(message) = res {
+            pamh.syslog(LOG_ERR, &message);
+        }
+    }
+    options
+}
+
+/// Returns the locale of the messages displayed to the user, from the environment.
+fn locale() -> String {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| "C".to_owned())
+}
+
+/// Returns the status of the module when the password could not be checked because of the
+/// error, `None` if the password was rejected by a check.
+fn failure_status(error: &Error) -> Option<c_int> {
+    match *error {
+        Error::UnknownUser { .. } => Some(PAM_USER_UNKNOWN),
+        ref error if error.is_rejection() => None,
+        _ => Some(PAM_SERVICE_ERR),
+    }
+}
+
+fn change_password(pamh: &Handle, flags: c_int, args: &[String]) -> c_int {
+    let pwq = match PWQuality::from_default_config() {
+        Ok(pwq) => pwq,
+        Err(e) => {
+            pamh.syslog(LOG_ERR, &format!("cannot read the configuration: {:?}", e));
+            return PAM_SERVICE_ERR;
+        },
+    };
+    let options = configure(pamh, &pwq, args);
+    rules::install(&pwq, &options);
+
+    let user = match pamh.user() {
+        Ok(user) => user,
+        Err(res) => return res,
+    };
+    let old_password = pamh.item(PAM_OLDAUTHTOK);
+    let uid = unsafe { libc::getuid() };
+
+    let mut res = PAM_AUTHTOK_ERR;
+    for _ in 0..pwq.get_retry_times().max(1) {
+        let password = match pamh.new_password() {
+            Ok(password) => password,
+            Err(e) => {
+                pamh.syslog(LOG_NOTICE, "cannot obtain the new password");
+                return e;
+            },
+        };
+        let check = match pwq.check_for_user_as(password, old_password.clone(), &user, uid) {
+            Ok(check) => check,
+            Err(e) => {
+                pamh.syslog(LOG_ERR, &format!("cannot check the new password of {}: {:?}", user, e));
+                return failure_status(&e).unwrap_or(PAM_SERVICE_ERR);
+            },
+        };
+        if let Some(error) = check.error() {
+            if let Some(status) = failure_status(error) {
+                pamh.syslog(LOG_ERR, &format!("cannot check the new password of {}: {:?}", user, error));
+                return status;
+            }
+            pamh.error(&format!("BAD PASSWORD: {}", pwq.error_message(error, &locale())));
+            if options.debug {
+                pamh.syslog(LOG_NOTICE, &format!("bad password for {}: {:?}", user, error));
+            }
+            // an expired password must be changed for a valid one, even by root
+            let enforced = match check.bypass() {
+                None => true,
+                Some(Bypass::Root) => flags & PAM_CHANGE_EXPIRED_AUTHTOK != 0,
+                Some(_) => false,
+            };
+            if enforced {
+                pamh.clear_item(PAM_AUTHTOK);
+                res = PAM_AUTHTOK_ERR;
+                continue;
+            }
+        }
+        match pamh.verify_password() {
+            Ok(_) => return PAM_SUCCESS,
+            Err(e) => {
+                pamh.syslog(LOG_NOTICE, "the new passwords do not match");
+                pamh.clear_item(PAM_AUTHTOK);
+                res = e;
+            },
+        }
+    }
+    res
+}
+
+fn arguments(argc: c_int, argv: *const *const c_char) -> Vec<String> {
+    if argv.is_null() || argc <= 0 {
+        return Vec::new();
+    }
+    let args = unsafe { slice::from_raw_parts(argv, argc as usize) };
+    args.iter()
+        .filter(|arg| !arg.is_null())
+        .map(|&arg| unsafe { CStr::from_ptr(arg).to_string_lossy().into_owned() })
+        .collect()
+}
+
+/// Entry point of the `password` management group.
+#[no_mangle]
+pub extern "C" fn pam_sm_chauthtok(pamh: *mut pam_handle_t, flags: c_int, argc: c_int, argv: *const *const c_char) -> c_int {
+    // the password is only checked when it is updated
+    if flags & PAM_PRELIM_CHECK != 0 {
+        return PAM_SUCCESS;
+    }
+    let handle = Handle::new(pamh);
+    let args = arguments(argc, argv);
+    // never unwind across the FFI boundary
+    std::panic::catch_unwind(|| change_password(&handle, flags, &args)).unwrap_or(PAM_SERVICE_ERR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_statuses() {
+        assert_eq!(failure_status(&Error::MinLength), None);
+        assert_eq!(failure_status(&Error::BadWords { word: Some("acme".to_owned()) }), None);
+        assert_eq!(failure_status(&Error::Custom { code: 1, message: "Custom rule".to_owned() }), None);
+        assert_eq!(failure_status(&Error::UnknownUser { user: "john".to_owned() }), Some(PAM_USER_UNKNOWN));
+        assert_eq!(failure_status(&Error::NoPolicy { user: "john".to_owned() }), Some(PAM_SERVICE_ERR));
+        assert_eq!(failure_status(&Error::CfgfileOpen), Some(PAM_SERVICE_ERR));
+        assert_eq!(failure_status(&Error::Cancelled), Some(PAM_SERVICE_ERR));
+        assert_eq!(failure_status(&Error::UnknownError(-42)), Some(PAM_SERVICE_ERR));
+    }
+
+}
@@ -0,0 +1,131 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Binding for the parts of Linux-PAM used by the module.
+
+use libc::{c_char, c_int, c_void};
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+/// The opaque handle of a PAM transaction.
+#[allow(non_camel_case_types)]
+pub enum pam_handle_t {}
+
+pub const PAM_SUCCESS: c_int = 0;
+pub const PAM_SERVICE_ERR: c_int = 3;
+pub const PAM_USER_UNKNOWN: c_int = 10;
+pub const PAM_AUTHTOK_ERR: c_int = 20;
+
+pub const PAM_AUTHTOK: c_int = 6;
+pub const PAM_OLDAUTHTOK: c_int = 7;
+
+pub const PAM_ERROR_MSG: c_int = 3;
+
+pub const PAM_CHANGE_EXPIRED_AUTHTOK: c_int = 0x0020;
+pub const PAM_PRELIM_CHECK: c_int = 0x4000;
+
+pub const LOG_ERR: c_int = 3;
+pub const LOG_NOTICE: c_int = 5;
+
+#[link(name = "pam")]
+extern "C" {
+    fn pam_get_user(pamh: *mut pam_handle_t, user: *mut *const c_char, prompt: *const c_char) -> c_int;
+    fn pam_get_item(pamh: *const pam_handle_t, item_type: c_int, item: *mut *const c_void) -> c_int;
+    fn pam_set_item(pamh: *mut pam_handle_t, item_type: c_int, item: *const c_void) -> c_int;
+    fn pam_get_authtok_noverify(pamh: *mut pam_handle_t, authtok: *mut *const c_char, prompt: *const c_char) -> c_int;
+    fn pam_get_authtok_verify(pamh: *mut pam_handle_t, authtok: *mut *const c_char, prompt: *const c_char) -> c_int;
+    fn pam_prompt(pamh: *mut pam_handle_t, style: c_int, response: *mut *mut c_char, fmt: *const c_char, ...) -> c_int;
+    fn pam_syslog(pamh: *const pam_handle_t, priority: c_int, fmt: *const c_char, ...);
+}
+
+fn to_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        None
+    } else {
+        Some(unsafe { CStr::from_ptr(ptr).to_string_lossy().into_owned() })
+    }
+}
+
+/// A PAM transaction, as seen by the module.
+pub struct Handle(*mut pam_handle_t);
+
+impl Handle {
+
+    pub fn new(pamh: *mut pam_handle_t) -> Self {
+        Handle(pamh)
+    }
+
+    /// Returns the name of the user whose password is changed.
+    pub fn user(&self) -> Result<String, c_int> {
+        let mut user = ptr::null();
+        let res = unsafe { pam_get_user(self.0, &mut user, ptr::null()) };
+        if res != PAM_SUCCESS {
+            return Err(res);
+        }
+        to_string(user).ok_or(PAM_USER_UNKNOWN)
+    }
+
+    /// Returns the item of the given type, if it is set.
+    pub fn item(&self, item_type: c_int) -> Option<String> {
+        let mut item = ptr::null();
+        let res = unsafe { pam_get_item(self.0, item_type, &mut item) };
+        if res == PAM_SUCCESS {
+            to_string(item as *const c_char)
+        } else {
+            None
+        }
+    }
+
+    /// Clears the item of the given type.
+    pub fn clear_item(&self, item_type: c_int) {
+        unsafe {
+            pam_set_item(self.0, item_type, ptr::null());
+        }
+    }
+
+    /// Prompts the user for the new password, unless it is already set
+    /// (e.g. with the `use_authtok` option).
+    pub fn new_password(&self) -> Result<String, c_int> {
+        let mut authtok = ptr::null();
+        let res = unsafe { pam_get_authtok_noverify(self.0, &mut authtok, ptr::null()) };
+        if res != PAM_SUCCESS {
+            return Err(res);
+        }
+        to_string(authtok).ok_or(PAM_AUTHTOK_ERR)
+    }
+
+    /// Prompts the user to retype the new password, and checks it is the same.
+    pub fn verify_password(&self) -> Result<String, c_int> {
+        // the retyped password is compared to the one pointed to by `authtok`
+        let mut authtok: *const c_char = ptr::null();
+        let res = unsafe { pam_get_item(self.0, PAM_AUTHTOK, &mut authtok as *mut *const c_char as *mut *const c_void) };
+        if res != PAM_SUCCESS || authtok.is_null() {
+            return Err(PAM_AUTHTOK_ERR);
+        }
+        let res = unsafe { pam_get_authtok_verify(self.0, &mut authtok, ptr::null()) };
+        if res != PAM_SUCCESS {
+            return Err(res);
+        }
+        to_string(authtok).ok_or(PAM_AUTHTOK_ERR)
+    }
+
+    /// Displays an error message to the user.
+    pub fn error(&self, message: &str) {
+        if let Ok(message) = CString::new(message) {
+            unsafe {
+                pam_prompt(self.0, PAM_ERROR_MSG, ptr::null_mut(), b"%s\0".as_ptr() as *const c_char, message.as_ptr());
+            }
+        }
+    }
+
+    /// Logs a message to syslog.
+    pub fn syslog(&self, priority: c_int, message: &str) {
+        if let Ok(message) = CString::new(message) {
+            unsafe {
+                pam_syslog(self.0, priority, b"%s\0".as_ptr() as *const c_char, message.as_ptr());
+            }
+        }
+    }
+
+}
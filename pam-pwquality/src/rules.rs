@@ -0,0 +1,79 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Site-specific rules of the module.
+//!
+//! The rules added here are run after the checks configured in `pwquality.conf` and in the
+//! arguments of the module, for every password change. Rules implement `pwquality::rule::Rule`,
+//! for instance:
+//!
+//! ```ignore
+//! pwq.add_rule(|ctx: &CheckContext| if ctx.password().contains("acme") {
+//!     RuleOutcome::Reject { code: 1, message: "The password contains the company name".to_owned() }
+//! } else {
+//!     RuleOutcome::Pass
+//! });
+//! ```
+//!
+//! The module provides the `min_unique=<count>` rule, rejecting passwords with less than
+//! `count` different characters.
+
+use pwquality::PWQuality;
+use pwquality::rule::{CheckContext, Rule, RuleOutcome};
+
+use Options;
+
+use std::collections::HashSet;
+
+/// The code of the errors of the `min_unique` rule.
+pub const MIN_UNIQUE: i32 = 1;
+
+/// Rejects passwords with less than the given number of different characters.
+#[derive(Clone, Copy, Debug)]
+pub struct MinUnique(pub usize);
+
+impl Rule for MinUnique {
+    fn check(&self, ctx: &CheckContext) -> RuleOutcome {
+        let unique = ctx.password().chars().collect::<HashSet<char>>().len();
+        if unique < self.0 {
+            RuleOutcome::Reject {
+                code: MIN_UNIQUE,
+                message: format!("The password contains less than {} different characters", self.0),
+            }
+        } else {
+            RuleOutcome::Pass
+        }
+    }
+}
+
+/// Adds the site-specific rules to the instance used to check passwords.
+pub fn install(pwq: &PWQuality, options: &Options) {
+    if let Some(count) = options.min_unique {
+        pwq.add_rule(MinUnique(count));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use pwquality_sys::Error;
+
+    #[test]
+    fn min_unique() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(6);
+        install(&pwq, &Options { min_unique: Some(8), ..Options::default() });
+        assert_eq!(pwq.check("aaaa-bbbb-1A".to_owned(), None, None), Err(Error::Custom {
+            code: MIN_UNIQUE,
+            message: "The password contains less than 8 different characters".to_owned(),
+        }));
+        assert!(pwq.check("abcd-efgh-1A".to_owned(), None, None).is_ok());
+        // no rule without the argument
+        let pwq = PWQuality::new();
+        pwq.set_min_length(6);
+        install(&pwq, &Options::default());
+        assert!(pwq.check("aaaa-bbbb-1A".to_owned(), None, None).is_ok());
+    }
+
+}
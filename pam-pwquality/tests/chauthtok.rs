@@ -0,0 +1,188 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Password changes going through Linux-PAM with the built module.
+//!
+//! The test service of `pam.d/pwquality-test` is installed in a temporary directory and
+//! loaded with `pam_start_confdir` (Linux-PAM 1.4 or later), so neither root privileges nor
+//! `pam_wrapper` are needed. These tests use the configuration of the system in
+//! `/etc/security/pwquality.conf` and must run as a local user, they are therefore ignored
+//! by default:
+//!
+//! ```sh
+//! cargo test -p pam_pwquality_rs -- --ignored
+//! ```
+
+extern crate libc;
+
+use libc::{c_char, c_int, c_void};
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::env;
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::path::PathBuf;
+use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_CONV_ERR: c_int = 19;
+const PAM_AUTHTOK_ERR: c_int = 20;
+
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_PROMPT_ECHO_ON: c_int = 2;
+const PAM_ERROR_MSG: c_int = 3;
+
+#[allow(non_camel_case_types)]
+enum pam_handle_t {}
+
+#[repr(C)]
+struct PamMessage {
+    msg_style: c_int,
+    msg: *const c_char,
+}
+
+#[repr(C)]
+struct PamResponse {
+    resp: *mut c_char,
+    resp_retcode: c_int,
+}
+
+#[repr(C)]
+struct PamConv {
+    conv: extern "C" fn(c_int, *mut *const PamMessage, *mut *mut PamResponse, *mut c_void) -> c_int,
+    appdata_ptr: *mut c_void,
+}
+
+#[link(name = "pam")]
+extern "C" {
+    fn pam_start_confdir(service: *const c_char, user: *const c_char, conv: *const PamConv, confdir: *const c_char, pamh: *mut *mut pam_handle_t) -> c_int;
+    fn pam_chauthtok(pamh: *mut pam_handle_t, flags: c_int) -> c_int;
+    fn pam_end(pamh: *mut pam_handle_t, status: c_int) -> c_int;
+}
+
+// the answers to the prompts, and the error messages displayed
+#[derive(Default)]
+struct Conversation {
+    answers: RefCell<VecDeque<String>>,
+    errors: RefCell<Vec<String>>,
+}
+
+extern "C" fn converse(num_msg: c_int, msg: *mut *const PamMessage, resp: *mut *mut PamResponse, appdata: *mut c_void) -> c_int {
+    let conversation = unsafe { &*(appdata as *const Conversation) };
+    unsafe {
+        // the responses are freed by Linux-PAM
+        let responses = libc::calloc(num_msg as usize, std::mem::size_of::<PamResponse>()) as *mut PamResponse;
+        for i in 0..num_msg as usize {
+            let message = &**msg.add(i);
+            let text = CStr::from_ptr(message.msg).to_string_lossy().into_owned();
+            match message.msg_style {
+                PAM_PROMPT_ECHO_OFF | PAM_PROMPT_ECHO_ON => match conversation.answers.borrow_mut().pop_front() {
+                    Some(answer) => {
+                        let answer = CString::new(answer).unwrap();
+                        (*responses.add(i)).resp = libc::strdup(answer.as_ptr());
+                    },
+                    None => {
+                        libc::free(responses as *mut c_void);
+                        return PAM_CONV_ERR;
+                    },
+                },
+                PAM_ERROR_MSG => conversation.errors.borrow_mut().push(text),
+                _ => (),
+            }
+        }
+        *resp = responses;
+    }
+    PAM_SUCCESS
+}
+
+// the path of the module, built next to the test executable
+fn module() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    let deps = exe.parent().unwrap();
+    [deps.parent().unwrap(), deps].iter()
+        .map(|dir| dir.join("libpam_pwquality_rs.so"))
+        .find(|path| path.exists())
+        .expect("the module is not built")
+}
+
+// changes the password of the current user, answering the prompts with the given passwords,
+// and returns the status and the error messages displayed
+fn change_password(answers: &[&str]) -> (c_int, Vec<String>) {
+    change_password_with("", answers)
+}
+
+// same as `change_password`, with the given additional arguments of the module
+fn change_password_with(args: &str, answers: &[&str]) -> (c_int, Vec<String>) {
+    // the tests run concurrently, each one with its own configuration directory
+    static RUN: AtomicUsize = AtomicUsize::new(0);
+    let run = RUN.fetch_add(1, Ordering::SeqCst);
+    let confdir = env::temp_dir().join(format!("pam_pwquality_rs-{}-{}", std::process::id(), run));
+    fs::create_dir_all(&confdir).unwrap();
+    let module = format!("{} {}", module().to_str().unwrap(), args);
+    let service = include_str!("../pam.d/pwquality-test").replace("@MODULE@", module.trim_end());
+    fs::write(confdir.join("pwquality-test"), service).unwrap();
+
+    let user = unsafe { CStr::from_ptr((*libc::getpwuid(libc::getuid())).pw_name).to_owned() };
+    let conversation = Conversation::default();
+    conversation.answers.borrow_mut().extend(answers.iter().map(|&answer| answer.to_owned()));
+    let conv = PamConv {
+        conv: converse,
+        appdata_ptr: &conversation as *const Conversation as *mut c_void,
+    };
+    let service = CString::new("pwquality-test").unwrap();
+    let confdir_path = CString::new(confdir.to_str().unwrap()).unwrap();
+    let status = unsafe {
+        let mut pamh = ptr::null_mut();
+        let res = pam_start_confdir(service.as_ptr(), user.as_ptr(), &conv, confdir_path.as_ptr(), &mut pamh);
+        assert_eq!(res, PAM_SUCCESS);
+        let status = pam_chauthtok(pamh, 0);
+        pam_end(pamh, status);
+        status
+    };
+    fs::remove_dir_all(&confdir).unwrap();
+    let errors = conversation.errors.into_inner();
+    (status, errors)
+}
+
+#[test]
+#[ignore]
+fn accepts_good_password() {
+    let (status, errors) = change_password(&["vE7#kq-Lz9!wRm2", "vE7#kq-Lz9!wRm2"]);
+    assert_eq!(status, PAM_SUCCESS, "{:?}", errors);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+#[ignore]
+fn retries_after_bad_password() {
+    let (status, errors) = change_password(&["short", "vE7#kq-Lz9!wRm2", "vE7#kq-Lz9!wRm2"]);
+    assert_eq!(status, PAM_SUCCESS, "{:?}", errors);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].starts_with("BAD PASSWORD: "), "{:?}", errors);
+}
+
+#[test]
+#[ignore]
+fn rejects_after_retries() {
+    let (status, errors) = change_password(&["short", "tiny", "small"]);
+    assert_eq!(status, PAM_AUTHTOK_ERR, "{:?}", errors);
+    assert_eq!(errors.len(), 3, "{:?}", errors);
+    assert!(errors.iter().all(|error| error.starts_with("BAD PASSWORD: ")), "{:?}", errors);
+}
+
+#[test]
+#[ignore]
+fn rejects_mismatched_retype() {
+    let (status, _) = change_password(&["vE7#kq-Lz9!wRm2", "vE7#kq-Lz9!wRm3"]);
+    assert_ne!(status, PAM_SUCCESS);
+}
+
+#[test]
+#[ignore]
+fn site_rules() {
+    let (status, errors) = change_password_with("min_unique=10", &["aaaa-bbbb-cccc-1A", "vE7#kq-Lz9!wRm2", "vE7#kq-Lz9!wRm2"]);
+    assert_eq!(status, PAM_SUCCESS, "{:?}", errors);
+    assert_eq!(errors, ["BAD PASSWORD: The password contains less than 10 different characters"]);
+}
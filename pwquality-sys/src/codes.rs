@@ -0,0 +1,320 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Codes, names and kinds of errors.
+//!
+//! The errors returned by the library have its code (from `-1` to `-29`). The errors raised by
+//! the `pwquality` crate have codes from `-101`, out of the range of the library. The codes from
+//! `-101` to `-199` are reserved for them: `Error::from_int` builds the errors without details
+//! from their code, and an `UnknownError` with a reserved code stands for the error with this
+//! code whose details are missing. The name of an error is the snake case name of its variant
+//! (e.g. `min_digits`), as used by the `serde` feature.
+
+use Error;
+
+use std::convert::TryFrom;
+use std::error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// The kind of an error, telling who should handle it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The settings or the configuration are invalid, or do not apply (e.g. no policy applies
+    /// to a user).
+    Configuration,
+    /// The password was rejected by a check.
+    Rejection,
+    /// The library, the system or the operation failed.
+    Internal,
+}
+
+/// The error returned when an error cannot be built from a code or a name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The code or name is not the one of an error.
+    Unknown(String),
+    /// The error with the given name has details, it cannot be built from its code or name only.
+    MissingDetails(&'static str),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConversionError::Unknown(ref code) => write!(f, "unknown error {}", code),
+            ConversionError::MissingDetails(name) => write!(f, "error {} needs details", name),
+        }
+    }
+}
+
+impl error::Error for ConversionError {}
+
+const BREACHED: i32 = -101;
+const CUSTOM: i32 = -102;
+const KEYBOARD_WALK: i32 = -103;
+const UNSATISFIABLE: i32 = -104;
+const CANCELLED: i32 = -105;
+const HISTORY: i32 = -106;
+const NO_POLICY: i32 = -107;
+const UNKNOWN_USER: i32 = -108;
+const UNKNOWN_POLICY: i32 = -109;
+const IO: i32 = -110;
+
+// the range of codes reserved for the errors of the `pwquality` crate
+const RESERVED: RangeInclusive<i32> = -199..=-101;
+
+// the codes of the errors with details, which cannot be built from their code
+const WITH_DETAILS: &[(i32, &str)] = &[
+    (BREACHED, "breached"),
+    (CUSTOM, "custom"),
+    (UNSATISFIABLE, "unsatisfiable"),
+    (HISTORY, "history"),
+    (NO_POLICY, "no_policy"),
+    (UNKNOWN_USER, "unknown_user"),
+    (UNKNOWN_POLICY, "unknown_policy"),
+    (IO, "io"),
+];
+
+impl Error {
+
+    /// Returns the code of this error. The code of an `UnknownError` is the one returned
+    /// by the library, the code of a `Custom` error is not the one given by the rule.
+    /// `Error::try_from(error.code())` returns this error, without the word of a `BadWords`
+    /// error, or a `MissingDetails` error with the name of the error if it has details.
+    pub fn code(&self) -> i32 {
+        match *self {
+            Error::FatalFailure => -1,
+            Error::Integer => -2,
+            Error::CfgfileOpen => -3,
+            Error::CfgfileMalformed => -4,
+            Error::UnknownSetting => -5,
+            Error::NonIntSetting => -6,
+            Error::NonStrSetting => -7,
+            Error::MemAlloc => -8,
+            Error::TooSimilar => -9,
+            Error::MinDigits => -10,
+            Error::MinUppers => -11,
+            Error::MinLowers => -12,
+            Error::MinOthers => -13,
+            Error::MinLength => -14,
+            Error::Palindrome => -15,
+            Error::CaseChangesOnly => -16,
+            Error::Rotated => -17,
+            Error::MinClasses => -18,
+            Error::MaxConsecutive => -19,
+            Error::EmptyPassword => -20,
+            Error::SamePassword => -21,
+            Error::CracklibCheck => -22,
+            Error::Rng => -23,
+            Error::GenerationFailed => -24,
+            Error::UserCheck => -25,
+            Error::GecosCheck => -26,
+            Error::MaxClassRepeat => -27,
+            Error::BadWords { .. } => -28,
+            Error::MaxSequence => -29,
+            Error::Breached { .. } => BREACHED,
+            Error::Custom { .. } => CUSTOM,
+            Error::KeyboardWalk => KEYBOARD_WALK,
+            Error::Unsatisfiable { .. } => UNSATISFIABLE,
+            Error::Cancelled => CANCELLED,
+            Error::History { .. } => HISTORY,
+            Error::NoPolicy { .. } => NO_POLICY,
+            Error::UnknownUser { .. } => UNKNOWN_USER,
+            Error::UnknownPolicy { .. } => UNKNOWN_POLICY,
+            Error::Io { .. } => IO,
+            Error::UnknownError(code) => code,
+        }
+    }
+
+    /// Returns the name of this error, i.e. the snake case name of its variant.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Error::FatalFailure => "fatal_failure",
+            Error::Integer => "integer",
+            Error::CfgfileOpen => "cfgfile_open",
+            Error::CfgfileMalformed => "cfgfile_malformed",
+            Error::UnknownSetting => "unknown_setting",
+            Error::NonIntSetting => "non_int_setting",
+            Error::NonStrSetting => "non_str_setting",
+            Error::MemAlloc => "mem_alloc",
+            Error::TooSimilar => "too_similar",
+            Error::MinDigits => "min_digits",
+            Error::MinUppers => "min_uppers",
+            Error::MinLowers => "min_lowers",
+            Error::MinOthers => "min_others",
+            Error::MinLength => "min_length",
+            Error::Palindrome => "palindrome",
+            Error::CaseChangesOnly => "case_changes_only",
+            Error::Rotated => "rotated",
+            Error::MinClasses => "min_classes",
+            Error::MaxConsecutive => "max_consecutive",
+            Error::EmptyPassword => "empty_password",
+            Error::SamePassword => "same_password",
+            Error::CracklibCheck => "cracklib_check",
+            Error::Rng => "rng",
+            Error::GenerationFailed => "generation_failed",
+            Error::UserCheck => "user_check",
+            Error::GecosCheck => "gecos_check",
+            Error::MaxClassRepeat => "max_class_repeat",
+            Error::BadWords { .. } => "bad_words",
+            Error::MaxSequence => "max_sequence",
+            Error::Breached { .. } => "breached",
+            Error::Custom { .. } => "custom",
+            Error::KeyboardWalk => "keyboard_walk",
+            Error::Unsatisfiable { .. } => "unsatisfiable",
+            Error::Cancelled => "cancelled",
+            Error::History { .. } => "history",
+            Error::NoPolicy { .. } => "no_policy",
+            Error::UnknownUser { .. } => "unknown_user",
+            Error::UnknownPolicy { .. } => "unknown_policy",
+            Error::Io { .. } => "io",
+            Error::UnknownError(_) => "unknown_error",
+        }
+    }
+
+    /// Returns the kind of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match *self {
+            Error::Integer | Error::CfgfileOpen | Error::CfgfileMalformed | Error::UnknownSetting
+                | Error::NonIntSetting | Error::NonStrSetting | Error::Unsatisfiable { .. }
+                | Error::NoPolicy { .. } | Error::UnknownUser { .. } | Error::UnknownPolicy { .. } => ErrorKind::Configuration,
+            Error::TooSimilar | Error::MinDigits | Error::MinUppers | Error::MinLowers
+                | Error::MinOthers | Error::MinLength | Error::Palindrome | Error::CaseChangesOnly
+                | Error::Rotated | Error::MinClasses | Error::MaxConsecutive | Error::EmptyPassword
+                | Error::SamePassword | Error::CracklibCheck | Error::UserCheck | Error::GecosCheck
+                | Error::MaxClassRepeat | Error::BadWords { .. } | Error::MaxSequence | Error::Breached { .. }
+                | Error::Custom { .. } | Error::KeyboardWalk | Error::History { .. } => ErrorKind::Rejection,
+            Error::FatalFailure | Error::MemAlloc | Error::Rng | Error::GenerationFailed
+                | Error::Cancelled | Error::Io { .. } | Error::UnknownError(_) => ErrorKind::Internal,
+        }
+    }
+
+    /// Returns whether the password was rejected by a check.
+    pub fn is_rejection(&self) -> bool {
+        self.kind() == ErrorKind::Rejection
+    }
+
+    /// Returns whether the settings or the configuration are invalid.
+    pub fn is_configuration(&self) -> bool {
+        self.kind() == ErrorKind::Configuration
+    }
+
+    /// Returns whether the library, the system or the operation failed.
+    pub fn is_internal(&self) -> bool {
+        self.kind() == ErrorKind::Internal
+    }
+
+}
+
+// the error of the `pwquality` crate without details with the given code
+pub(crate) fn from_reserved(code: i32) -> Option<Error> {
+    match code {
+        KEYBOARD_WALK => Some(Error::KeyboardWalk),
+        CANCELLED => Some(Error::Cancelled),
+        _ => None,
+    }
+}
+
+impl TryFrom<i32> for Error {
+    type Error = ConversionError;
+
+    /// Returns the error with the given code. Unlike `from_int`, an unknown code is an error.
+    fn try_from(code: i32) -> Result<Self, ConversionError> {
+        match Error::from_int(code) {
+            Error::UnknownError(_) => match WITH_DETAILS.iter().find(|&&(c, _)| c == code) {
+                Some(&(_, name)) => Err(ConversionError::MissingDetails(name)),
+                None => Err(ConversionError::Unknown(code.to_string())),
+            },
+            error => Ok(error),
+        }
+    }
+}
+
+impl FromStr for Error {
+    type Err = ConversionError;
+
+    /// Returns the error with the given name.
+    fn from_str(name: &str) -> Result<Self, ConversionError> {
+        for code in (-29..=-1).chain(RESERVED) {
+            if let Ok(error) = Error::try_from(code) {
+                if error.name() == name {
+                    return Ok(error);
+                }
+            }
+        }
+        match WITH_DETAILS.iter().find(|&&(_, n)| n == name) {
+            Some(&(_, name)) => Err(ConversionError::MissingDetails(name)),
+            None if name == "unknown_error" => Err(ConversionError::MissingDetails("unknown_error")),
+            None => Err(ConversionError::Unknown(name.to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_round_trip() {
+        for error in Error::examples() {
+            match Error::try_from(error.code()) {
+                Ok(Error::BadWords { word: None }) => assert_eq!(error.name(), "bad_words"),
+                Ok(converted) => assert_eq!(converted, error),
+                Err(ConversionError::MissingDetails(name)) => assert_eq!(name, error.name()),
+                Err(ConversionError::Unknown(code)) => assert_eq!(error, Error::UnknownError(code.parse().unwrap())),
+            }
+        }
+        assert_eq!(Error::try_from(-28), Ok(Error::BadWords { word: None }));
+        assert_eq!(Error::try_from(-103), Ok(Error::KeyboardWalk));
+        assert_eq!(Error::try_from(-101), Err(ConversionError::MissingDetails("breached")));
+        assert_eq!(Error::try_from(-42), Err(ConversionError::Unknown("-42".to_owned())));
+    }
+
+    #[test]
+    fn reserved_codes() {
+        for code in RESERVED {
+            let error = Error::from_int(code);
+            assert_eq!(error.code(), code);
+            match error {
+                Error::UnknownError(_) => assert!(Error::try_from(code).is_err()),
+                error => assert_eq!(Error::try_from(code), Ok(error)),
+            }
+        }
+        assert_eq!(Error::from_int(-103), Error::KeyboardWalk);
+        assert_eq!(Error::from_int(-105), Error::Cancelled);
+        for code in (-300..=0).filter(|code| !RESERVED.contains(code)) {
+            assert_eq!(Error::from_int(code).code(), code);
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for error in Error::examples() {
+            let name = error.name();
+            match name.parse::<Error>() {
+                Ok(parsed) => assert_eq!(parsed, Error::try_from(error.code()).unwrap()),
+                Err(ConversionError::MissingDetails(missing)) => assert_eq!(missing, name),
+                Err(error) => panic!("{}: {}", name, error),
+            }
+        }
+        assert_eq!("keyboard_walk".parse(), Ok(Error::KeyboardWalk));
+        assert_eq!("bad_words".parse(), Ok(Error::BadWords { word: None }));
+        assert_eq!("unknown_error".parse::<Error>(), Err(ConversionError::MissingDetails("unknown_error")));
+        assert_eq!("bad_word".parse::<Error>(), Err(ConversionError::Unknown("bad_word".to_owned())));
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(Error::MinLength.kind(), ErrorKind::Rejection);
+        assert_eq!(Error::CfgfileOpen.kind(), ErrorKind::Configuration);
+        assert_eq!(Error::UnknownUser { user: "john".to_owned() }.kind(), ErrorKind::Configuration);
+        assert!(Error::UnknownPolicy { policy: "admins".to_owned() }.is_configuration());
+        assert!(Error::Io { message: "permission denied".to_owned() }.is_internal());
+        assert_eq!(Error::Cancelled.kind(), ErrorKind::Internal);
+        assert!(Error::History { index: 0, reason: Box::new(Error::SamePassword) }.is_rejection());
+        assert!(Error::NoPolicy { user: "john".to_owned() }.is_configuration());
+        assert!(Error::UnknownError(-42).is_internal());
+    }
+
+}
@@ -1,6 +1,15 @@
 // Copyright (c) 2017, Lucas Satabin
 // Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
 extern crate libc;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+
+mod codes;
+mod messages;
+
+pub use codes::{ConversionError, ErrorKind};
+pub use messages::{Language, MessageParams};
 
 use libc::{c_char, c_void, c_int, size_t};
 
@@ -20,11 +29,22 @@ pub static PWQ_SETTING_MAX_SEQUENCE: c_int = 14;
 pub static PWQ_SETTING_DICT_CHECK: c_int = 15;
 pub static PWQ_SETTING_USER_CHECK: c_int = 16;
 pub static PWQ_SETTING_ENFORCING: c_int = 17;
+pub static PWQ_SETTING_RETRY_TIMES: c_int = 18;
+pub static PWQ_SETTING_ENFORCE_ROOT: c_int = 19;
+pub static PWQ_SETTING_LOCAL_USERS: c_int = 20;
 
 /// An error returned by the underlying library.
 /// The `UnknownError` should never be returned unless a new error
 /// code is added in `pwquality`.
-#[derive(Clone, Debug)]
+/// The variants after `MaxSequence` are never returned by the library itself,
+/// they are raised by the additional checks of the `pwquality` crate.
+///
+/// With the `serde` feature, an error is serialized as an object with the snake case name of
+/// the variant as `code` (e.g. `min_digits`, `cracklib_check`), and its fields, if any, as
+/// `details`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "code", content = "details", rename_all = "snake_case"))]
 pub enum Error {
     FatalFailure, // = -1;
     Integer, // = -2;
@@ -53,9 +73,35 @@ pub enum Error {
     UserCheck, // = -25;
     GecosCheck, // = -26;
     MaxClassRepeat, // = -27;
-    BadWords, // = -28;
+    /// The password contains a forbidden word, in some form. The word is given when it is
+    /// known, i.e. when it was found by the `pwquality` crate.
+    BadWords { word: Option<String> }, // = -28;
     MaxSequence, // = -29;
 
+    /// The password appears at least `count` times in a breached passwords list.
+    Breached { count: u64 },
+    /// The password was rejected by a custom rule, with the given code and message.
+    Custom { code: i32, message: String },
+    /// The password contains a walk on adjacent keys of a keyboard longer than allowed.
+    KeyboardWalk,
+    /// No password can be generated with the given specification that satisfies the settings.
+    Unsatisfiable { reason: String },
+    /// The operation was cancelled, or its worker stopped, before it completed.
+    Cancelled,
+    /// The password reuses the previous password at the given index in the history,
+    /// for the given reason.
+    History { index: usize, reason: Box<Error> },
+    /// No policy of a policy set applies to the given user.
+    NoPolicy { user: String },
+    /// The given user does not exist.
+    UnknownUser { user: String },
+    /// A policy set has no policy with the given name.
+    UnknownPolicy { policy: String },
+    /// A system call or a read failed with the given message.
+    Io { message: String },
+
+    /// An error with a code unknown to this crate, or an error of the `pwquality` crate with
+    /// the given code whose details are missing.
     UnknownError(i32),
 }
 
@@ -90,12 +136,34 @@ impl Error {
             -25 => Error::UserCheck,
             -26 => Error::GecosCheck,
             -27 => Error::MaxClassRepeat,
-            -28 => Error::BadWords,
+            -28 => Error::BadWords { word: None },
             -29 => Error::MaxSequence,
-            _ => Error::UnknownError(i)
+            _ => codes::from_reserved(i).unwrap_or(Error::UnknownError(i))
         }
     }
 
+    /// Returns an error of each variant, with details, for the tests of this crate and of
+    /// the crates using its errors.
+    #[doc(hidden)]
+    pub fn examples() -> Vec<Error> {
+        let mut errors: Vec<Error> = (-29..=-1).map(Error::from_int).collect();
+        errors.extend(vec![
+            Error::BadWords { word: Some("secret".to_owned()) },
+            Error::Breached { count: 3 },
+            Error::Custom { code: 1, message: "Custom rule".to_owned() },
+            Error::KeyboardWalk,
+            Error::Unsatisfiable { reason: "reason".to_owned() },
+            Error::Cancelled,
+            Error::History { index: 2, reason: Box::new(Error::TooSimilar) },
+            Error::NoPolicy { user: "john".to_owned() },
+            Error::UnknownUser { user: "john".to_owned() },
+            Error::UnknownPolicy { policy: "admins".to_owned() },
+            Error::Io { message: "permission denied".to_owned() },
+            Error::UnknownError(-42),
+        ]);
+        errors
+    }
+
 }
 
 /// The opaque settings structure, instantiated by `pwquality`.
@@ -106,6 +174,7 @@ extern {
     pub fn pwquality_default_settings() -> *const pwquality_settings_t;
     pub fn pwquality_free_settings(pwq: *const pwquality_settings_t);
     pub fn pwquality_read_config(pwq: *const pwquality_settings_t, cfgfile: *const c_char, auxerror: *mut *mut c_void) -> c_int;
+    pub fn pwquality_set_option(pwq: *const pwquality_settings_t, option: *const c_char) -> c_int;
     pub fn pwquality_set_int_value(pwq: *const pwquality_settings_t, setting: c_int, value: c_int) -> c_int;
     pub fn pwquality_set_str_value(pwq: *const pwquality_settings_t, setting: c_int, value: *const c_char) -> c_int;
     pub fn pwquality_get_int_value(pwq: *const pwquality_settings_t, setting: c_int, value: *mut c_int) -> c_int;
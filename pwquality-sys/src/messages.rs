@@ -0,0 +1,488 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Messages describing errors, independent of the process locale.
+//!
+//! `pwquality_strerror` translates its messages with the gettext catalogue of the system,
+//! according to the locale of the process. The messages here are shipped with the crate,
+//! and the language is given for each message instead.
+
+use Error;
+
+/// A language of the message catalogue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+impl Language {
+
+    /// Returns the language of the given locale, which can be a language tag (e.g. `fr`,
+    /// `de-AT`) or a POSIX locale name (e.g. `es_ES.UTF-8`).
+    /// English is returned for unsupported languages.
+    pub fn from_locale(locale: &str) -> Self {
+        let language = locale.split(['-', '_', '.', '@']).next().unwrap_or("");
+        match language.to_lowercase().as_str() {
+            "fr" => Language::French,
+            "de" => Language::German,
+            "es" => Language::Spanish,
+            _ => Language::English,
+        }
+    }
+
+    /// Returns the ISO 639-1 code of the language.
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Spanish => "es",
+        }
+    }
+
+    /// Returns the index of the language in the texts of a catalogue: English, French, German
+    /// and Spanish.
+    pub fn index(self) -> usize {
+        match self {
+            Language::English => 0,
+            Language::French => 1,
+            Language::German => 2,
+            Language::Spanish => 3,
+        }
+    }
+
+    /// Returns the text with the given name in this language, in English if it is not
+    /// translated, from a catalogue giving the texts of each name in the order of `index`.
+    pub fn lookup(self, catalogue: &[(&str, [&'static str; 4])], name: &str) -> Option<&'static str> {
+        let texts = catalogue.iter().find(|&&(n, _)| n == name).map(|(_, texts)| texts)?;
+        Some(Some(texts[self.index()]).filter(|text| !text.is_empty()).unwrap_or(texts[0]))
+    }
+
+}
+
+/// The values of the settings interpolated in the messages.
+/// A message without its value is less precise, e.g. "The password is too short"
+/// instead of "The password is shorter than 12 characters".
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageParams {
+    /// The minimum length of a password.
+    pub min_length: Option<i32>,
+    /// The minimum number of digits.
+    pub min_digits: Option<i32>,
+    /// The minimum number of uppercase letters.
+    pub min_uppers: Option<i32>,
+    /// The minimum number of lowercase letters.
+    pub min_lowers: Option<i32>,
+    /// The minimum number of other characters.
+    pub min_others: Option<i32>,
+    /// The minimum number of character classes.
+    pub min_classes: Option<i32>,
+    /// The maximum number of same consecutive characters.
+    pub max_repeat: Option<i32>,
+    /// The maximum number of consecutive characters of the same class.
+    pub max_class_repeat: Option<i32>,
+    /// The maximum length of a monotonic character sequence.
+    pub max_sequence: Option<i32>,
+    /// The maximum length of a keyboard walk.
+    pub max_keyboard_walk: Option<i32>,
+}
+
+// the messages in English, French, German and Spanish, by name of error, `{}` being replaced
+// by the details of the error
+static MESSAGES: &[(&str, [&str; 4])] = &[
+    ("fatal_failure", [
+        "Fatal failure",
+        "Échec fatal",
+        "Schwerwiegender Fehler",
+        "Fallo fatal"]),
+    ("integer", [
+        "Bad integer value of setting",
+        "Valeur entière du paramètre incorrecte",
+        "Ungültiger ganzzahliger Wert der Einstellung",
+        "Valor entero del parámetro incorrecto"]),
+    ("cfgfile_open", [
+        "Cannot open configuration file",
+        "Impossible d'ouvrir le fichier de configuration",
+        "Die Konfigurationsdatei kann nicht geöffnet werden",
+        "No se puede abrir el archivo de configuración"]),
+    ("cfgfile_malformed", [
+        "The configuration file is malformed",
+        "Le fichier de configuration est mal formé",
+        "Die Konfigurationsdatei ist fehlerhaft",
+        "El archivo de configuración está mal formado"]),
+    ("unknown_setting", [
+        "Unknown setting",
+        "Paramètre inconnu",
+        "Unbekannte Einstellung",
+        "Parámetro desconocido"]),
+    ("non_int_setting", [
+        "Setting is not of integer type",
+        "Le paramètre n'est pas de type entier",
+        "Die Einstellung ist nicht vom Typ Ganzzahl",
+        "El parámetro no es de tipo entero"]),
+    ("non_str_setting", [
+        "Setting is not of string type",
+        "Le paramètre n'est pas de type chaîne",
+        "Die Einstellung ist nicht vom Typ Zeichenkette",
+        "El parámetro no es de tipo cadena"]),
+    ("mem_alloc", [
+        "Memory allocation error",
+        "Erreur d'allocation mémoire",
+        "Fehler bei der Speicherzuweisung",
+        "Error de asignación de memoria"]),
+    ("too_similar", [
+        "The password is too similar to the old one",
+        "Le mot de passe est trop similaire à l'ancien",
+        "Das Passwort ist dem alten zu ähnlich",
+        "La contraseña es demasiado parecida a la anterior"]),
+    ("min_digits", [
+        "The password contains too few digits",
+        "Le mot de passe contient trop peu de chiffres",
+        "Das Passwort enthält zu wenige Ziffern",
+        "La contraseña contiene muy pocos dígitos"]),
+    ("min_uppers", [
+        "The password contains too few uppercase letters",
+        "Le mot de passe contient trop peu de majuscules",
+        "Das Passwort enthält zu wenige Großbuchstaben",
+        "La contraseña contiene muy pocas letras mayúsculas"]),
+    ("min_lowers", [
+        "The password contains too few lowercase letters",
+        "Le mot de passe contient trop peu de minuscules",
+        "Das Passwort enthält zu wenige Kleinbuchstaben",
+        "La contraseña contiene muy pocas letras minúsculas"]),
+    ("min_others", [
+        "The password contains too few non-alphanumeric characters",
+        "Le mot de passe contient trop peu de caractères non alphanumériques",
+        "Das Passwort enthält zu wenige Sonderzeichen",
+        "La contraseña contiene muy pocos caracteres no alfanuméricos"]),
+    ("min_length", [
+        "The password is too short",
+        "Le mot de passe est trop court",
+        "Das Passwort ist zu kurz",
+        "La contraseña es demasiado corta"]),
+    ("palindrome", [
+        "The password is a palindrome",
+        "Le mot de passe est un palindrome",
+        "Das Passwort ist ein Palindrom",
+        "La contraseña es un palíndromo"]),
+    ("case_changes_only", [
+        "The password differs with case changes only",
+        "Le mot de passe ne diffère que par la casse",
+        "Das Passwort unterscheidet sich nur durch Groß- und Kleinschreibung",
+        "La contraseña solo difiere en mayúsculas y minúsculas"]),
+    ("rotated", [
+        "The password is just rotated old one",
+        "Le mot de passe est une simple rotation de l'ancien",
+        "Das Passwort ist nur eine Verschiebung des alten",
+        "La contraseña es solo una rotación de la anterior"]),
+    ("min_classes", [
+        "The password does not contain enough character classes",
+        "Le mot de passe ne contient pas assez de classes de caractères",
+        "Das Passwort enthält nicht genügend Zeichenklassen",
+        "La contraseña no contiene suficientes clases de caracteres"]),
+    ("max_consecutive", [
+        "The password contains too many same characters consecutively",
+        "Le mot de passe contient trop de caractères identiques consécutifs",
+        "Das Passwort enthält zu viele gleiche aufeinanderfolgende Zeichen",
+        "La contraseña contiene demasiados caracteres iguales consecutivos"]),
+    ("max_class_repeat", [
+        "The password contains too many characters of the same class consecutively",
+        "Le mot de passe contient trop de caractères consécutifs de la même classe",
+        "Das Passwort enthält zu viele aufeinanderfolgende Zeichen derselben Klasse",
+        "La contraseña contiene demasiados caracteres consecutivos de la misma clase"]),
+    ("max_sequence", [
+        "The password contains too long of a monotonic character sequence",
+        "Le mot de passe contient une séquence monotone de caractères trop longue",
+        "Das Passwort enthält eine zu lange monotone Zeichenfolge",
+        "La contraseña contiene una secuencia monótona de caracteres demasiado larga"]),
+    ("empty_password", [
+        "No password supplied",
+        "Aucun mot de passe fourni",
+        "Kein Passwort angegeben",
+        "No se ha proporcionado ninguna contraseña"]),
+    ("same_password", [
+        "The password is the same as the old one",
+        "Le mot de passe est identique à l'ancien",
+        "Das Passwort ist identisch mit dem alten",
+        "La contraseña es igual a la anterior"]),
+    ("cracklib_check", [
+        "The password fails the dictionary check",
+        "Le mot de passe ne passe pas la vérification par dictionnaire",
+        "Das Passwort besteht die Wörterbuchprüfung nicht",
+        "La contraseña no supera la comprobación de diccionario"]),
+    ("rng", [
+        "Cannot obtain random numbers from the RNG device",
+        "Impossible d'obtenir des nombres aléatoires du générateur",
+        "Vom Zufallszahlengenerator können keine Zufallszahlen bezogen werden",
+        "No se pueden obtener números aleatorios del generador"]),
+    ("generation_failed", [
+        "Password generation failed - required entropy too low for settings",
+        "La génération du mot de passe a échoué - entropie demandée trop faible pour les paramètres",
+        "Passworterzeugung fehlgeschlagen - geforderte Entropie für die Einstellungen zu gering",
+        "La generación de la contraseña ha fallado - entropía requerida demasiado baja para los parámetros"]),
+    ("user_check", [
+        "The password contains the user name in some form",
+        "Le mot de passe contient le nom d'utilisateur sous une forme quelconque",
+        "Das Passwort enthält den Benutzernamen in irgendeiner Form",
+        "La contraseña contiene el nombre de usuario de alguna forma"]),
+    ("gecos_check", [
+        "The password contains words from the real name of the user in some form",
+        "Le mot de passe contient des mots du nom réel de l'utilisateur sous une forme quelconque",
+        "Das Passwort enthält Wörter aus dem echten Namen des Benutzers in irgendeiner Form",
+        "La contraseña contiene palabras del nombre real del usuario de alguna forma"]),
+    ("bad_words", [
+        "The password contains forbidden words in some form",
+        "Le mot de passe contient des mots interdits sous une forme quelconque",
+        "Das Passwort enthält verbotene Wörter in irgendeiner Form",
+        "La contraseña contiene palabras prohibidas de alguna forma"]),
+    ("breached", [
+        "The password appears {} times in a list of breached passwords",
+        "Le mot de passe figure {} fois dans une liste de mots de passe compromis",
+        "Das Passwort steht {}-mal in einer Liste kompromittierter Passwörter",
+        "La contraseña figura {} veces en una lista de contraseñas filtradas"]),
+    ("keyboard_walk", [
+        "The password contains a walk on adjacent keyboard keys",
+        "Le mot de passe contient une suite de touches adjacentes du clavier",
+        "Das Passwort enthält eine Folge benachbarter Tasten der Tastatur",
+        "La contraseña contiene una secuencia de teclas adyacentes del teclado"]),
+    ("unsatisfiable", [
+        "No password satisfying the settings can be generated: {}",
+        "Aucun mot de passe respectant les paramètres ne peut être généré : {}",
+        "Es kann kein Passwort erzeugt werden, das die Einstellungen erfüllt: {}",
+        "No se puede generar ninguna contraseña que cumpla los parámetros: {}"]),
+    ("cancelled", [
+        "The operation was cancelled",
+        "L'opération a été annulée",
+        "Der Vorgang wurde abgebrochen",
+        "La operación se ha cancelado"]),
+    ("history", [
+        "The password was used before: {}",
+        "Le mot de passe a déjà été utilisé : {}",
+        "Das Passwort wurde bereits verwendet: {}",
+        "La contraseña ya se ha utilizado: {}"]),
+    ("no_policy", [
+        "No password policy applies to the user {}",
+        "Aucune politique de mot de passe ne s'applique à l'utilisateur {}",
+        "Für den Benutzer {} gilt keine Passwortrichtlinie",
+        "Ninguna política de contraseñas se aplica al usuario {}"]),
+    ("unknown_user", [
+        "Unknown user {}",
+        "Utilisateur inconnu {}",
+        "Unbekannter Benutzer {}",
+        "Usuario desconocido {}"]),
+    ("unknown_policy", [
+        "Unknown password policy {}",
+        "Politique de mot de passe inconnue {}",
+        "Unbekannte Passwortrichtlinie {}",
+        "Política de contraseñas desconocida {}"]),
+    ("io", [
+        "System error: {}",
+        "Erreur système : {}",
+        "Systemfehler: {}",
+        "Error del sistema: {}"]),
+    ("unknown_error", [
+        "Unknown error ({})",
+        "Erreur inconnue ({})",
+        "Unbekannter Fehler ({})",
+        "Error desconocido ({})"]),
+];
+
+// the more precise messages given when the value of the setting, or the detail of the error, is
+// known, `{}` being replaced by this value
+static MESSAGES_WITH_VALUE: &[(&str, [&str; 4])] = &[
+    ("min_digits", [
+        "The password contains less than {} digits",
+        "Le mot de passe contient moins de {} chiffres",
+        "Das Passwort enthält weniger als {} Ziffern",
+        "La contraseña contiene menos de {} dígitos"]),
+    ("min_uppers", [
+        "The password contains less than {} uppercase letters",
+        "Le mot de passe contient moins de {} majuscules",
+        "Das Passwort enthält weniger als {} Großbuchstaben",
+        "La contraseña contiene menos de {} letras mayúsculas"]),
+    ("min_lowers", [
+        "The password contains less than {} lowercase letters",
+        "Le mot de passe contient moins de {} minuscules",
+        "Das Passwort enthält weniger als {} Kleinbuchstaben",
+        "La contraseña contiene menos de {} letras minúsculas"]),
+    ("min_others", [
+        "The password contains less than {} non-alphanumeric characters",
+        "Le mot de passe contient moins de {} caractères non alphanumériques",
+        "Das Passwort enthält weniger als {} Sonderzeichen",
+        "La contraseña contiene menos de {} caracteres no alfanuméricos"]),
+    ("min_length", [
+        "The password is shorter than {} characters",
+        "Le mot de passe fait moins de {} caractères",
+        "Das Passwort ist kürzer als {} Zeichen",
+        "La contraseña tiene menos de {} caracteres"]),
+    ("min_classes", [
+        "The password contains less than {} character classes",
+        "Le mot de passe contient moins de {} classes de caractères",
+        "Das Passwort enthält weniger als {} Zeichenklassen",
+        "La contraseña contiene menos de {} clases de caracteres"]),
+    ("max_consecutive", [
+        "The password contains more than {} same characters consecutively",
+        "Le mot de passe contient plus de {} caractères identiques consécutifs",
+        "Das Passwort enthält mehr als {} gleiche aufeinanderfolgende Zeichen",
+        "La contraseña contiene más de {} caracteres iguales consecutivos"]),
+    ("max_class_repeat", [
+        "The password contains more than {} characters of the same class consecutively",
+        "Le mot de passe contient plus de {} caractères consécutifs de la même classe",
+        "Das Passwort enthält mehr als {} aufeinanderfolgende Zeichen derselben Klasse",
+        "La contraseña contiene más de {} caracteres consecutivos de la misma clase"]),
+    ("max_sequence", [
+        "The password contains monotonic sequence longer than {} characters",
+        "Le mot de passe contient une séquence monotone de plus de {} caractères",
+        "Das Passwort enthält eine monotone Zeichenfolge mit mehr als {} Zeichen",
+        "La contraseña contiene una secuencia monótona de más de {} caracteres"]),
+    ("keyboard_walk", [
+        "The password contains a walk on more than {} adjacent keyboard keys",
+        "Le mot de passe contient une suite de plus de {} touches adjacentes du clavier",
+        "Das Passwort enthält eine Folge von mehr als {} benachbarten Tasten der Tastatur",
+        "La contraseña contiene una secuencia de más de {} teclas adyacentes del teclado"]),
+    ("bad_words", [
+        "The password contains the forbidden word '{}' in some form",
+        "Le mot de passe contient le mot interdit « {} » sous une forme quelconque",
+        "Das Passwort enthält das verbotene Wort „{}“ in irgendeiner Form",
+        "La contraseña contiene la palabra prohibida «{}» de alguna forma"]),
+];
+
+fn text(language: Language, name: &'static str) -> &'static str {
+    language.lookup(MESSAGES, name).unwrap_or(name)
+}
+
+// the message with the given name, or its variant with the value if it is known
+fn with_value(language: Language, name: &'static str, value: Option<&str>) -> String {
+    match value.and_then(|value| language.lookup(MESSAGES_WITH_VALUE, name).map(|text| text.replace("{}", value))) {
+        Some(message) => message,
+        None => text(language, name).to_owned(),
+    }
+}
+
+// the message with the given name, or its variant with the value of the setting if it is positive
+fn with_limit(language: Language, name: &'static str, limit: Option<i32>) -> String {
+    with_value(language, name, limit.filter(|&limit| limit > 0).map(|limit| limit.to_string()).as_deref())
+}
+
+impl Error {
+
+    /// Returns the message describing this error in the language of the given locale
+    /// (see `Language::from_locale`), without the values of the settings.
+    pub fn message(&self, locale: &str) -> String {
+        self.message_with(locale, &MessageParams::default())
+    }
+
+    /// Returns the message describing this error in the language of the given locale,
+    /// with the values of the settings given in the parameters.
+    /// The message of a `Custom` error is returned as is.
+    pub fn message_with(&self, locale: &str, params: &MessageParams) -> String {
+        let language = Language::from_locale(locale);
+        let name = self.name();
+        let with = |value: &str| text(language, name).replace("{}", value);
+        match *self {
+            Error::MinDigits => with_limit(language, name, params.min_digits),
+            Error::MinUppers => with_limit(language, name, params.min_uppers),
+            Error::MinLowers => with_limit(language, name, params.min_lowers),
+            Error::MinOthers => with_limit(language, name, params.min_others),
+            Error::MinLength => with_limit(language, name, params.min_length),
+            Error::MinClasses => with_limit(language, name, params.min_classes),
+            Error::MaxConsecutive => with_limit(language, name, params.max_repeat),
+            Error::MaxClassRepeat => with_limit(language, name, params.max_class_repeat),
+            Error::MaxSequence => with_limit(language, name, params.max_sequence),
+            Error::KeyboardWalk => with_limit(language, name, params.max_keyboard_walk),
+            Error::Breached { count } => with(&count.to_string()),
+            Error::Custom { ref message, .. } => message.clone(),
+            Error::Unsatisfiable { ref reason } => with(reason),
+            Error::History { ref reason, .. } => with(&reason.message_with(locale, params)),
+            Error::NoPolicy { ref user } => with(user),
+            Error::UnknownUser { ref user } => with(user),
+            Error::UnknownPolicy { ref policy } => with(policy),
+            Error::Io { ref message } => with(message),
+            Error::BadWords { ref word } => with_value(language, name, word.as_deref()),
+            Error::UnknownError(code) => with(&code.to_string()),
+            _ => text(language, name).to_owned(),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LANGUAGES: [Language; 4] = [Language::English, Language::French, Language::German, Language::Spanish];
+
+    fn params() -> MessageParams {
+        MessageParams {
+            min_length: Some(12),
+            min_digits: Some(1),
+            min_uppers: Some(1),
+            min_lowers: Some(1),
+            min_others: Some(1),
+            min_classes: Some(3),
+            max_repeat: Some(2),
+            max_class_repeat: Some(4),
+            max_sequence: Some(3),
+            max_keyboard_walk: Some(3),
+        }
+    }
+
+    #[test]
+    fn catalogue_is_complete() {
+        for error in Error::examples() {
+            let name = error.name();
+            assert!(name == "custom" || MESSAGES.iter().any(|&(n, _)| n == name), "no message for {}", name);
+        }
+        for (name, texts) in MESSAGES.iter().chain(MESSAGES_WITH_VALUE) {
+            for (language, text) in LANGUAGES.iter().zip(texts) {
+                assert!(!text.is_empty(), "no {:?} message for {}", language, name);
+            }
+        }
+        for &(name, _) in MESSAGES_WITH_VALUE {
+            assert!(MESSAGES.iter().any(|&(n, _)| n == name), "no message without value for {}", name);
+        }
+    }
+
+    #[test]
+    fn messages_are_translated() {
+        for error in Error::examples() {
+            for params in &[MessageParams::default(), params()] {
+                for language in &LANGUAGES {
+                    let message = error.message_with(language.code(), params);
+                    assert!(!message.is_empty() && message != error.name(), "{:?} in {:?}", error, language);
+                    assert!(!message.contains("{}"), "{:?} in {:?}: {}", error, language, message);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn messages_with_details() {
+        let params = params();
+        assert_eq!(Error::MinLength.message("en"), "The password is too short");
+        assert_eq!(Error::MinLength.message_with("fr_FR.UTF-8", &params), "Le mot de passe fait moins de 12 caractères");
+        assert_eq!(Error::MinLength.message_with("en", &MessageParams { min_length: Some(0), ..params }),
+                   Error::MinLength.message("en"));
+        assert!(Error::Breached { count: 3 }.message("de").contains('3'));
+        assert_eq!(Error::BadWords { word: Some("acme".to_owned()) }.message("en"),
+                   "The password contains the forbidden word 'acme' in some form");
+        assert_eq!(Error::BadWords { word: None }.message("en"), "The password contains forbidden words in some form");
+        assert_eq!(Error::Custom { code: 1, message: "Custom rule".to_owned() }.message("es"), "Custom rule");
+        let history = Error::History { index: 0, reason: Box::new(Error::SamePassword) };
+        assert!(history.message("en").contains(&Error::SamePassword.message("en")));
+    }
+
+    #[test]
+    fn fallback_to_english() {
+        let table: &[(&str, [&'static str; 4])] = &[("partial", ["in English", "en français", "", ""])];
+        assert_eq!(Language::French.lookup(table, "partial"), Some("en français"));
+        assert_eq!(Language::German.lookup(table, "partial"), Some("in English"));
+        assert_eq!(Language::German.lookup(table, "missing"), None);
+        assert_eq!(text(Language::Spanish, "missing"), "missing");
+        assert_eq!(Language::from_locale("pt_BR"), Language::English);
+    }
+
+}
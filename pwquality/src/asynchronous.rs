@@ -0,0 +1,438 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Asynchronous checks and generation, available with the `async` feature.
+//!
+//! Checks performed by the library read dictionaries from disk and may take milliseconds,
+//! which must not happen on the threads of an asynchronous executor. An `AsyncPWQuality`
+//! runs them on a dedicated pool of workers and returns futures that can be awaited from
+//! any runtime.
+//!
+//! As a `PWQuality` instance cannot be shared between threads, every worker creates its own
+//! instance with the factory given at creation. The number of workers bounds the number of
+//! operations running concurrently, the other ones wait in a queue.
+//! The queue is unbounded, so that submitting an operation never blocks the executor:
+//! callers that accept requests from untrusted sources should limit the number of pending
+//! operations themselves.
+//!
+//! Dropping a pending future cancels its operation if it has not started yet. An operation
+//! that has started runs to completion, and its result is discarded.
+//!
+//! Workers are started on threads by default. With the `tokio-runtime` feature, `TokioSpawner` starts
+//! them on the blocking pool of a tokio runtime instead.
+
+use pwquality_sys::Error;
+
+use report::CheckReport;
+use PWQuality;
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+type Job = Box<dyn FnOnce(&PWQuality) + Send>;
+
+/// Starts the workers of an `AsyncPWQuality`.
+/// A worker runs until the `AsyncPWQuality` is dropped.
+pub trait Spawner {
+    /// Starts a worker.
+    fn spawn(&self, worker: Box<dyn FnOnce() + Send>);
+}
+
+/// Starts each worker on its own thread.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadSpawner;
+
+impl Spawner for ThreadSpawner {
+    fn spawn(&self, worker: Box<dyn FnOnce() + Send>) {
+        thread::Builder::new()
+            .name("pwquality".to_owned())
+            .spawn(worker)
+            .expect("failed to spawn pwquality worker thread");
+    }
+}
+
+/// Starts the workers on the blocking pool of a tokio runtime.
+/// The runtime waits for its blocking tasks when it shuts down, so the `AsyncPWQuality`
+/// must be dropped before the runtime.
+#[cfg(feature = "tokio")]
+#[derive(Clone, Debug)]
+pub struct TokioSpawner {
+    handle: ::tokio::runtime::Handle,
+}
+
+#[cfg(feature = "tokio")]
+impl TokioSpawner {
+
+    /// Creates a spawner starting workers on the runtime of the given handle.
+    pub fn new(handle: ::tokio::runtime::Handle) -> Self {
+        TokioSpawner {
+            handle
+        }
+    }
+
+    /// Creates a spawner starting workers on the current runtime.
+    /// Panics if not called from within a tokio runtime.
+    pub fn current() -> Self {
+        TokioSpawner::new(::tokio::runtime::Handle::current())
+    }
+
+}
+
+#[cfg(feature = "tokio")]
+impl Spawner for TokioSpawner {
+    fn spawn(&self, worker: Box<dyn FnOnce() + Send>) {
+        self.handle.spawn_blocking(worker);
+    }
+}
+
+#[derive(Debug)]
+struct Slot<T> {
+    result: Option<Result<T, Error>>,
+    waker: Option<Waker>,
+    cancelled: bool,
+    done: bool,
+}
+
+/// The future result of an operation run by an `AsyncPWQuality`.
+/// It resolves to `Error::Cancelled` if the worker running the operation stopped before
+/// completing it.
+#[derive(Debug)]
+pub struct Pending<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Pending<T> {
+
+    /// Cancels the operation if it has not started yet.
+    /// The future then resolves to `Error::Cancelled`.
+    pub fn cancel(&self) {
+        let mut slot = self.slot.lock().unwrap();
+        slot.cancelled = true;
+        if slot.result.is_none() {
+            slot.result = Some(Err(Error::Cancelled));
+        }
+        if let Some(waker) = slot.waker.take() {
+            waker.wake();
+        }
+    }
+
+}
+
+impl<T> Future for Pending<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut slot = self.slot.lock().unwrap();
+        match slot.result.take() {
+            Some(result) => Poll::Ready(result),
+            None if slot.done => Poll::Ready(Err(Error::Cancelled)),
+            None => {
+                slot.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl<T> Drop for Pending<T> {
+    fn drop(&mut self) {
+        if let Ok(mut slot) = self.slot.lock() {
+            slot.cancelled = true;
+        }
+    }
+}
+
+// completes the slot when dropped, so that the future resolves even if the operation panics
+struct Completion<T> {
+    slot: Arc<Mutex<Slot<T>>>,
+}
+
+impl<T> Completion<T> {
+
+    fn is_cancelled(&self) -> bool {
+        self.slot.lock().map(|slot| slot.cancelled).unwrap_or(true)
+    }
+
+    fn complete(&self, result: Result<T, Error>) {
+        if let Ok(mut slot) = self.slot.lock() {
+            if !slot.cancelled {
+                slot.result = Some(result);
+            }
+        }
+    }
+
+}
+
+impl<T> Drop for Completion<T> {
+    fn drop(&mut self) {
+        if let Ok(mut slot) = self.slot.lock() {
+            slot.done = true;
+            if let Some(waker) = slot.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+
+/// Runs checks and generation of `PWQuality` instances on a pool of workers.
+#[derive(Debug)]
+pub struct AsyncPWQuality {
+    sender: Mutex<Sender<Job>>,
+    workers: usize,
+}
+
+impl AsyncPWQuality {
+
+    /// Creates a pool of the given number of workers (at least 1) started on threads,
+    /// each with an instance created by the factory.
+    /// The error of the factory is returned if it fails to create an instance.
+    pub fn new<F>(workers: usize, factory: F) -> Result<Self, Error>
+        where F: Fn() -> Result<PWQuality, Error> + Send + Sync + 'static {
+        AsyncPWQuality::with_spawner(workers, factory, &ThreadSpawner)
+    }
+
+    /// Creates a pool of the given number of workers (at least 1) started with the spawner,
+    /// each with an instance created by the factory.
+    /// The error of the factory is returned if it fails to create an instance.
+    pub fn with_spawner<F, S>(workers: usize, factory: F, spawner: &S) -> Result<Self, Error>
+        where F: Fn() -> Result<PWQuality, Error> + Send + Sync + 'static,
+              S: Spawner + ?Sized {
+        let workers = workers.max(1);
+        let factory = Arc::new(factory);
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let (ready_sender, ready_receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let factory = factory.clone();
+            let receiver = receiver.clone();
+            let ready = ready_sender.clone();
+            spawner.spawn(Box::new(move || {
+                match factory() {
+                    Ok(pwq) => {
+                        let _ = ready.send(Ok(()));
+                        drop(ready);
+                        work(&pwq, &receiver);
+                    },
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                    },
+                }
+            }));
+        }
+        drop(ready_sender);
+        for _ in 0..workers {
+            ready_receiver.recv().map_err(|_| Error::Cancelled)??;
+        }
+        Ok(AsyncPWQuality {
+            sender: Mutex::new(sender),
+            workers,
+        })
+    }
+
+    /// Returns the number of workers, i.e. the maximum number of operations running concurrently.
+    pub fn workers(&self) -> usize {
+        self.workers
+    }
+
+    /// Runs the given operation on the instance of a worker.
+    /// The operation is queued without limit until a worker is available.
+    pub fn run<F, T>(&self, operation: F) -> Pending<T>
+        where F: FnOnce(&PWQuality) -> Result<T, Error> + Send + 'static,
+              T: Send + 'static {
+        let slot = Arc::new(Mutex::new(Slot {
+            result: None,
+            waker: None,
+            cancelled: false,
+            done: false,
+        }));
+        let completion = Completion {
+            slot: slot.clone(),
+        };
+        let job: Job = Box::new(move |pwq| {
+            if !completion.is_cancelled() {
+                completion.complete(operation(pwq));
+            }
+        });
+        // if the workers stopped, the job is dropped and the future resolves to `Cancelled`
+        let _ = self.sender.lock().unwrap().send(job);
+        Pending {
+            slot
+        }
+    }
+
+    /// Checks a password according to the settings and returns the computed score.
+    pub fn check(&self, password: String, old_password: Option<String>, username: Option<String>) -> Pending<i32> {
+        self.run(move |pwq| pwq.check(password, old_password, username))
+    }
+
+    /// Checks a password and returns the detailed result of the checks.
+    pub fn check_report(&self, password: String, old_password: Option<String>, username: Option<String>) -> Pending<CheckReport> {
+        self.run(move |pwq| Ok(pwq.check_report(password, old_password, username)))
+    }
+
+    /// Generates a password with the given number of bits of entropy.
+    pub fn generate_password(&self, entropy: i32) -> Pending<String> {
+        self.run(move |pwq| pwq.generate_password(entropy))
+    }
+
+    /// Generates a password with the given number of bits of entropy, that passes the checks.
+    pub fn generate_compliant(&self, entropy: i32) -> Pending<String> {
+        self.run(move |pwq| pwq.generate_compliant(entropy))
+    }
+
+}
+
+fn work(pwq: &PWQuality, receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // the lock is released before running the job
+        let job = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(_) => return,
+        };
+        match job {
+            // a panicking operation resolves its future to `Cancelled` and the worker goes on
+            Ok(job) => {
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| job(pwq)));
+            },
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::time::Duration;
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn pool(workers: usize) -> AsyncPWQuality {
+        AsyncPWQuality::new(workers, || Ok(PWQuality::new())).unwrap()
+    }
+
+    // occupies the only worker until the returned sender is dropped
+    fn block(pwq: &AsyncPWQuality) -> (Sender<()>, Pending<()>) {
+        let (sender, receiver) = mpsc::channel::<()>();
+        let (started_sender, started) = mpsc::channel();
+        let pending = pwq.run(move |_| {
+            started_sender.send(()).unwrap();
+            let _ = receiver.recv();
+            Ok(())
+        });
+        started.recv().unwrap();
+        (sender, pending)
+    }
+
+    #[test]
+    fn results() {
+        let pwq = pool(2);
+        let expected = PWQuality::new().check("Wq-7-Zt!x-Vb4".to_owned(), None, None);
+        assert_eq!(block_on(pwq.check("Wq-7-Zt!x-Vb4".to_owned(), None, None)), expected);
+        assert!(block_on(pwq.check("abc".to_owned(), None, None)).is_err());
+        assert_eq!(block_on(pwq.run(|pwq| Ok(pwq.get_min_length()))), Ok(PWQuality::new().get_min_length()));
+        assert_eq!(block_on(pwq.run(|_| Err::<(), _>(Error::GenerationFailed))), Err(Error::GenerationFailed));
+    }
+
+    #[test]
+    fn factory_error() {
+        match AsyncPWQuality::new(2, || Err(Error::CfgfileOpen)) {
+            Err(Error::CfgfileOpen) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn cancel() {
+        let pwq = pool(1);
+        let (release, blocking) = block(&pwq);
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        let pending = pwq.run(move |_| {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+        pending.cancel();
+        drop(release);
+        assert_eq!(block_on(blocking), Ok(()));
+        assert_eq!(block_on(pending), Err(Error::Cancelled));
+        // the worker went past the cancelled operation
+        assert_eq!(block_on(pwq.run(|_| Ok(42))), Ok(42));
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn drop_cancels() {
+        let pwq = pool(1);
+        let (release, blocking) = block(&pwq);
+        let ran = Arc::new(AtomicBool::new(false));
+        let flag = ran.clone();
+        drop(pwq.run(move |_| {
+            flag.store(true, Ordering::SeqCst);
+            Ok(())
+        }));
+        drop(release);
+        assert_eq!(block_on(blocking), Ok(()));
+        assert_eq!(block_on(pwq.run(|_| Ok(42))), Ok(42));
+        assert!(!ran.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn panicking_operation() {
+        let pwq = pool(1);
+        assert_eq!(block_on(pwq.run(|_| -> Result<(), Error> { panic!("operation failed") })), Err(Error::Cancelled));
+        assert_eq!(block_on(pwq.run(|_| Ok(42))), Ok(42));
+    }
+
+    #[test]
+    fn worker_limit() {
+        let pwq = pool(2);
+        assert_eq!(pwq.workers(), 2);
+        assert_eq!(pool(0).workers(), 1);
+        let running = Arc::new(AtomicUsize::new(0));
+        let max = Arc::new(AtomicUsize::new(0));
+        let pending: Vec<Pending<()>> = (0..6)
+            .map(|_| {
+                let running = running.clone();
+                let max = max.clone();
+                pwq.run(move |_| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    max.fetch_max(now, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(20));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(())
+                })
+            })
+            .collect();
+        for pending in pending {
+            assert_eq!(block_on(pending), Ok(()));
+        }
+        assert_eq!(max.load(Ordering::SeqCst), 2);
+    }
+
+}
@@ -0,0 +1,314 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Forbidden words matched through leetspeak and case changes.
+//!
+//! The bad words of the library are only looked for literally in the password, so `p@ssw0rd`
+//! does not match `password`. A `BadWordList` set with `PWQuality::set_bad_word_list` matches
+//! its words regardless of case, and each character of the password can stand for the letters
+//! given by a `SubstitutionTable` (e.g. `@` for `a`, `0` for `o`). With the `unicode` feature,
+//! the characters confusable with ASCII letters are folded and the diacritics removed, in the
+//! password as well as in the words.
+//! A password containing a bad word is rejected with a `BadWords` error giving the word.
+//!
+//! A `BadPhraseList`, loaded with `PWQuality::load_bad_words_file`, scales to lists of
+//! thousands of words or phrases (e.g. product or customer names), searched with an
+//! Aho-Corasick automaton, and reports the matched phrase the same way.
+
+use aho_corasick::AhoCorasick;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The letters each character of a password can stand for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubstitutionTable {
+    substitutions: HashMap<char, Vec<char>>,
+}
+
+impl SubstitutionTable {
+
+    /// Creates a table without substitutions.
+    pub fn new() -> Self {
+        SubstitutionTable::default()
+    }
+
+    /// Creates a table with the usual leetspeak substitutions (`4` and `@` for `a`, `3` for `e`,
+    /// `1` and `!` for `i` and `l`, `0` for `o`, `5` and `$` for `s`, `7` and `+` for `t`, …).
+    pub fn leet() -> Self {
+        let mut table = SubstitutionTable::new();
+        for &(from, to) in &[
+            ('4', "a"), ('@', "a"), ('8', "b"), ('(', "c"), ('<', "c"), ('3', "e"), ('6', "g"),
+            ('9', "g"), ('#', "h"), ('1', "il"), ('!', "il"), ('|', "il"), ('0', "o"), ('5', "s"),
+            ('$', "s"), ('7', "t"), ('+', "t"), ('2', "z"),
+        ] {
+            for to in to.chars() {
+                table.add(from, to);
+            }
+        }
+        table
+    }
+
+    /// Adds a substitution: the character `from` of a password can stand for the letter `to`.
+    pub fn add(&mut self, from: char, to: char) {
+        let letters = self.substitutions.entry(from).or_default();
+        let to = to.to_lowercase().next().unwrap_or(to);
+        if !letters.contains(&to) {
+            letters.push(to);
+        }
+    }
+
+    /// Returns the letters the character can stand for, besides itself.
+    pub fn substitutes(&self, c: char) -> &[char] {
+        self.substitutions.get(&c).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // whether the character of the password can stand for the letter of a word
+    fn matches(&self, c: char, letter: char) -> bool {
+        c == letter || self.substitutes(c).contains(&letter)
+    }
+
+}
+
+/// A list of forbidden words.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BadWordList {
+    words: Vec<String>,
+    table: SubstitutionTable,
+}
+
+impl BadWordList {
+
+    /// Creates a list of the given words, matched with the leetspeak substitutions.
+    pub fn new(words: Vec<String>) -> Self {
+        BadWordList::with_table(words, SubstitutionTable::leet())
+    }
+
+    /// Creates a list of the given words, matched with the given substitutions.
+    pub fn with_table(words: Vec<String>, table: SubstitutionTable) -> Self {
+        let mut list = BadWordList {
+            words: Vec::new(),
+            table,
+        };
+        for word in words {
+            list.add_word(word);
+        }
+        list
+    }
+
+    /// Adds a forbidden word. Empty words are ignored.
+    pub fn add_word<S: Into<String>>(&mut self, word: S) {
+        let word = fold(&word.into());
+        if !word.is_empty() && !self.words.contains(&word) {
+            self.words.push(word);
+        }
+    }
+
+    /// Returns the forbidden words, in lowercase and folded like the passwords.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Returns the substitutions the words are matched with.
+    pub fn table(&self) -> &SubstitutionTable {
+        &self.table
+    }
+
+    /// Returns the first forbidden word found in the password, if any.
+    pub fn find(&self, password: &str) -> Option<&str> {
+        let candidate: Vec<char> = fold(password).chars().collect();
+        self.words.iter()
+            .find(|word| {
+                let word: Vec<char> = word.chars().collect();
+                candidate.windows(word.len())
+                    .any(|window| window.iter().zip(&word).all(|(&c, &letter)| self.table.matches(c, letter)))
+            })
+            .map(String::as_str)
+    }
+
+}
+
+// the text as matched against the words, the words being folded the same way
+#[cfg(feature = "unicode")]
+fn fold(text: &str) -> String {
+    ::unicode::fold_confusables(text).to_lowercase()
+}
+
+#[cfg(not(feature = "unicode"))]
+fn fold(text: &str) -> String {
+    text.to_lowercase()
+}
+
+/// A large list of forbidden words and phrases, searched for efficiently.
+///
+/// The phrases are matched regardless of case, literally and with their whitespace removed
+/// (e.g. `acme corp` matches both `acme corp` and `AcmeCorp`), but without substitutions.
+#[derive(Clone, Debug)]
+pub struct BadPhraseList {
+    phrases: Vec<String>,
+    // the phrase of each pattern of the automaton
+    patterns: Vec<usize>,
+    automaton: AhoCorasick,
+}
+
+impl BadPhraseList {
+
+    /// Creates a list of the given phrases. Empty phrases are ignored.
+    pub fn new(phrases: Vec<String>) -> Self {
+        let mut seen = HashSet::new();
+        let unique: Vec<String> = phrases.iter()
+            .map(|phrase| fold(phrase.trim()))
+            .filter(|phrase| !phrase.is_empty() && seen.insert(phrase.clone()))
+            .collect();
+        let mut keys = Vec::new();
+        let mut patterns = Vec::new();
+        for (idx, phrase) in unique.iter().enumerate() {
+            keys.push(phrase.clone());
+            patterns.push(idx);
+            let joined: String = phrase.split_whitespace().collect();
+            if joined != *phrase {
+                keys.push(joined);
+                patterns.push(idx);
+            }
+        }
+        // the patterns are plain strings, building the automaton cannot fail
+        let automaton = AhoCorasick::new(&keys).expect("invalid bad phrases");
+        BadPhraseList {
+            phrases: unique,
+            patterns,
+            automaton,
+        }
+    }
+
+    /// Reads the phrases of the given content, one per line. Blank lines and lines starting
+    /// with `#` are ignored.
+    pub fn parse(content: &str) -> Self {
+        let phrases = content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect();
+        BadPhraseList::new(phrases)
+    }
+
+    /// Reads the phrases of the given file, see `parse`.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(BadPhraseList::parse(&content))
+    }
+
+    /// Returns the forbidden phrases, in lowercase and folded like the passwords.
+    pub fn phrases(&self) -> &[String] {
+        &self.phrases
+    }
+
+    /// Returns the first forbidden phrase found in the password, if any.
+    pub fn find(&self, password: &str) -> Option<&str> {
+        self.automaton.find(&fold(password))
+            .map(|found| self.phrases[self.patterns[found.pattern().as_usize()]].as_str())
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PWQuality;
+
+    use pwquality_sys::Error;
+
+    use std::env;
+
+    #[test]
+    fn leet_substitutions() {
+        let list = BadWordList::new(vec!["Password".to_owned(), "secret".to_owned(), "password".to_owned()]);
+        assert_eq!(list.words(), &["password".to_owned(), "secret".to_owned()]);
+        assert_eq!(list.find("my-p@ssw0rd!"), Some("password"));
+        assert_eq!(list.find("PASSWORD"), Some("password"));
+        assert_eq!(list.find("5ecr3t"), Some("secret"));
+        assert_eq!(list.find("s3cr3"), None);
+        assert_eq!(list.find("correct horse"), None);
+        // `1` stands for both `i` and `l`
+        assert_eq!(BadWordList::new(vec!["lili".to_owned()]).find("1111"), Some("lili"));
+    }
+
+    #[test]
+    fn custom_table() {
+        let mut table = SubstitutionTable::new();
+        table.add('%', 'X');
+        assert_eq!(table.substitutes('%'), &['x']);
+        assert!(table.substitutes('@').is_empty());
+        let list = BadWordList::with_table(vec!["xyz".to_owned()], table);
+        assert_eq!(list.find("%yz"), Some("xyz"));
+        assert_eq!(list.find("@yz"), None);
+        assert!(BadWordList::new(vec![String::new()]).words().is_empty());
+    }
+
+    #[test]
+    fn phrases() {
+        let list = BadPhraseList::parse("# products\nAcme Corp\n\n  widget  \nacme corp\nRoad Runner\n");
+        assert_eq!(list.phrases(), &["acme corp".to_owned(), "widget".to_owned(), "road runner".to_owned()]);
+        assert_eq!(list.find("I-love-ACME corp"), Some("acme corp"));
+        assert_eq!(list.find("AcmeCorp2024"), Some("acme corp"));
+        assert_eq!(list.find("roadrunner!"), Some("road runner"));
+        assert_eq!(list.find("xwidgetx"), Some("widget"));
+        assert_eq!(list.find("acme-corp"), None);
+        assert_eq!(list.find("w1dget"), None);
+    }
+
+    #[test]
+    fn many_phrases() {
+        let phrases: Vec<String> = (0..5000).map(|i| format!("c{}c", i)).chain(Some("C42C".to_owned())).collect();
+        let list = BadPhraseList::new(phrases);
+        assert_eq!(list.phrases().len(), 5000);
+        assert_eq!(list.find("xC4999cx"), Some("c4999c"));
+        assert_eq!(list.find("c42c"), Some("c42c"));
+        assert!(list.find("c5000c").is_none());
+    }
+
+    #[test]
+    fn reported_word() {
+        let pwq = PWQuality::new();
+        pwq.set_bad_word_list(Some(BadWordList::new(vec!["acme".to_owned()])));
+        assert_eq!(pwq.check("X-@cm3-Secure-91".to_owned(), None, None),
+                   Err(Error::BadWords { word: Some("acme".to_owned()) }));
+        pwq.set_bad_word_list(None);
+        pwq.set_bad_phrase_list(Some(BadPhraseList::new(vec!["road runner".to_owned()])));
+        assert_eq!(pwq.check("X-RoadRunner-91".to_owned(), None, None),
+                   Err(Error::BadWords { word: Some("road runner".to_owned()) }));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn folded_words() {
+        let list = BadWordList::new(vec!["Café".to_owned()]);
+        assert_eq!(list.words(), &["cafe".to_owned()]);
+        assert_eq!(list.find("café-42"), Some("cafe"));
+        assert_eq!(list.find("CAFE-42"), Some("cafe"));
+        // a Cyrillic `а`
+        assert_eq!(list.find("cаfé"), Some("cafe"));
+        let phrases = BadPhraseList::new(vec!["Crème Brûlée".to_owned()]);
+        assert_eq!(phrases.find("my crème brûlée"), Some("creme brulee"));
+        assert_eq!(phrases.find("CremeBrulee!"), Some("creme brulee"));
+    }
+
+    #[test]
+    fn load_file() {
+        let path = env::temp_dir().join(format!("pwquality-bad-words-{}.txt", std::process::id()));
+        let pwq = PWQuality::new();
+        assert_eq!(pwq.load_bad_words_file(&path), Err(Error::CfgfileOpen));
+        assert!(!pwq.has_bad_phrase_list());
+        fs::write(&path, b"acme\n\xff\n").unwrap();
+        match pwq.load_bad_words_file(&path) {
+            Err(Error::Io { .. }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        fs::write(&path, "# products\nacme\n").unwrap();
+        assert_eq!(pwq.load_bad_words_file(&path), Ok(()));
+        assert!(pwq.has_bad_phrase_list());
+        fs::remove_file(&path).unwrap();
+    }
+
+}
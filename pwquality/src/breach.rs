@@ -0,0 +1,145 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Offline check of passwords against a list of breached password hashes.
+//!
+//! The list must be in the format of the downloadable
+//! [Have I Been Pwned](https://haveibeenpwned.com/Passwords) files, ordered by hash:
+//! each line contains an hexadecimal hash, a colon and the number of times the password
+//! was seen in breaches (e.g. `000000005AD76BD555C1D6D771DE417A4B87E4B4:10`).
+//! No network access is ever performed, the file is memory mapped and searched by
+//! dichotomy.
+
+use md4::Md4;
+use memmap2::Mmap;
+use sha1::{Digest, Sha1};
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// The kind of hashes contained in a breached passwords list.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashKind {
+    /// SHA-1 hash of the UTF-8 encoded password.
+    Sha1,
+    /// NTLM hash, i.e. MD4 hash of the UTF-16LE encoded password.
+    Ntlm,
+}
+
+impl HashKind {
+
+    /// Returns the uppercase hexadecimal hash of the given password.
+    pub fn hash(&self, password: &str) -> String {
+        let digest = match *self {
+            HashKind::Sha1 => Sha1::digest(password.as_bytes()).to_vec(),
+            HashKind::Ntlm => {
+                let utf16: Vec<u8> = password.encode_utf16().flat_map(|u| u.to_le_bytes().to_vec()).collect();
+                Md4::digest(&utf16).to_vec()
+            }
+        };
+        digest.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+}
+
+/// A sorted list of breached password hashes, memory mapped from a file.
+#[derive(Debug)]
+pub struct BreachDatabase {
+    map: Mmap,
+    kind: HashKind,
+}
+
+impl BreachDatabase {
+
+    /// Opens the breached passwords list at the given path, containing hashes of the given kind.
+    pub fn open<P: AsRef<Path>>(path: P, kind: HashKind) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // the file is only read, and is not expected to be modified while mapped
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(BreachDatabase {
+            map,
+            kind
+        })
+    }
+
+    /// Returns the kind of hashes contained in this list.
+    pub fn kind(&self) -> HashKind {
+        self.kind
+    }
+
+    /// Returns the number of times the given password appears in the list,
+    /// `0` if it does not appear at all.
+    pub fn occurrences(&self, password: &str) -> u64 {
+        let hash = self.kind.hash(password);
+        self.lookup(hash.as_bytes())
+    }
+
+    fn lookup(&self, hash: &[u8]) -> u64 {
+        let data: &[u8] = &self.map;
+        let mut low = 0;
+        let mut high = data.len();
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let start = data[..mid].iter().rposition(|&b| b == b'\n').map(|i| i + 1).unwrap_or(0);
+            let end = data[mid..].iter().position(|&b| b == b'\n').map(|i| mid + i).unwrap_or(data.len());
+            let line = &data[start..end];
+            let (line_hash, count) = match line.iter().position(|&b| b == b':') {
+                Some(idx) => (&line[..idx], &line[idx + 1..]),
+                None => (line, &line[line.len()..]),
+            };
+            match compare_hashes(line_hash, hash) {
+                Ordering::Equal => return parse_count(count),
+                Ordering::Less => low = end + 1,
+                Ordering::Greater => high = start,
+            }
+        }
+        0
+    }
+
+}
+
+fn compare_hashes(line_hash: &[u8], hash: &[u8]) -> Ordering {
+    let line_hash = line_hash.iter().map(|b| b.to_ascii_uppercase());
+    line_hash.cmp(hash.iter().cloned())
+}
+
+fn parse_count(count: &[u8]) -> u64 {
+    count.iter()
+        .take_while(|b| b.is_ascii_digit())
+        .fold(0u64, |acc, b| acc.saturating_mul(10).saturating_add(u64::from(b - b'0')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn hashes() {
+        assert_eq!(HashKind::Sha1.hash("password"), "5BAA61E4C9B93F3F0682250B6CF8331B7EE68FD8");
+        assert_eq!(HashKind::Ntlm.hash("password"), "8846F7EAEE8FB117AD06BDD830B7586C");
+    }
+
+    #[test]
+    fn lookup() {
+        let path = env::temp_dir().join(format!("pwquality-breach-{}.txt", std::process::id()));
+        fs::write(&path, "\
+0000000000000000000000000000000000000000:1
+5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8:3730471
+7C4A8D09CA3762AF61E59520943DC26494F8941B:24230577
+FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF:2").unwrap();
+        let database = BreachDatabase::open(&path, HashKind::Sha1).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(database.occurrences("password"), 3730471);
+        assert_eq!(database.occurrences("123456"), 24230577);
+        assert_eq!(database.occurrences("correct horse battery staple"), 0);
+        assert_eq!(database.lookup(b"0000000000000000000000000000000000000000"), 1);
+        assert_eq!(database.lookup(b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF"), 2);
+    }
+
+}
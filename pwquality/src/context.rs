@@ -0,0 +1,216 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Attributes of the user whose password is checked.
+//!
+//! The library only knows about the username and the GECOS field found in
+//! the local `passwd` database. A `UserContext` allows to give more attributes
+//! explicitly, from which forbidden words are derived the same way the library
+//! does for the GECOS field: any word longer than 3 characters may not appear
+//! in the password, forward or reversed, regardless of case.
+
+/// A date of birth.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BirthDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+impl BirthDate {
+
+    /// Creates a new date of birth.
+    pub fn new(year: u16, month: u8, day: u8) -> Self {
+        BirthDate {
+            year,
+            month,
+            day
+        }
+    }
+
+    /// Returns the usual written forms of this date that may not appear in a password
+    /// (e.g. `1985`, `0312`, `1203`, `031285`, `19851203`, …).
+    pub fn forms(&self) -> Vec<String> {
+        let yyyy = format!("{:04}", self.year);
+        let yy = format!("{:02}", self.year % 100);
+        let mm = format!("{:02}", self.month);
+        let dd = format!("{:02}", self.day);
+        vec![
+            yyyy.clone(),
+            format!("{}{}", dd, mm),
+            format!("{}{}", mm, dd),
+            format!("{}{}{}", dd, mm, yy),
+            format!("{}{}{}", mm, dd, yy),
+            format!("{}{}{}", yy, mm, dd),
+            format!("{}{}{}", dd, mm, yyyy),
+            format!("{}{}{}", mm, dd, yyyy),
+            format!("{}{}{}", yyyy, mm, dd),
+        ]
+    }
+
+}
+
+/// The attributes of a user, used to forbid passwords derived from them.
+#[derive(Clone, Debug, Default)]
+pub struct UserContext {
+    /// The username, checked by the library if the user check is enabled.
+    pub username: Option<String>,
+    /// The full name of the user.
+    pub full_name: Option<String>,
+    /// The GECOS field of the user, used instead of the one given by the GECOS provider
+    /// of the instance, if any.
+    pub gecos: Option<String>,
+    /// The email address of the user.
+    pub email: Option<String>,
+    /// The date of birth of the user.
+    pub birth_date: Option<BirthDate>,
+    /// Words related to the organization of the user (company, team, product names, …).
+    pub org_words: Vec<String>,
+}
+
+impl UserContext {
+
+    /// Creates a new context for the given username.
+    pub fn new<S: Into<String>>(username: S) -> Self {
+        UserContext {
+            username: Some(username.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the words derived from the attributes other than the username,
+    /// that may not appear in a password, in lowercase.
+    pub fn forbidden_words(&self) -> Vec<String> {
+        let mut words = Vec::new();
+        if let Some(ref full_name) = self.full_name {
+            words.extend(split_words(full_name));
+        }
+        if let Some(ref gecos) = self.gecos {
+            words.extend(split_words(gecos));
+        }
+        if let Some(ref email) = self.email {
+            let mut parts = email.splitn(2, '@');
+            if let Some(local) = parts.next() {
+                words.push(local.to_owned());
+                words.extend(split_words(local));
+            }
+            if let Some(domain) = parts.next() {
+                // the top level domain is not significant
+                let labels: Vec<&str> = domain.split('.').collect();
+                let len = labels.len().saturating_sub(1).max(1);
+                words.extend(labels.into_iter().take(len).map(String::from));
+            }
+        }
+        if let Some(ref birth_date) = self.birth_date {
+            words.extend(birth_date.forms());
+        }
+        for word in &self.org_words {
+            words.extend(split_words(word));
+        }
+        let mut words: Vec<String> = words.into_iter()
+            .filter(|w| w.chars().count() > 3)
+            .map(|w| w.to_lowercase())
+            .collect();
+        words.sort();
+        words.dedup();
+        words
+    }
+
+    /// Returns the first forbidden word found in the password, if any.
+    pub fn find_forbidden_word(&self, password: &str) -> Option<String> {
+        self.forbidden_words().into_iter().find(|word| contains_word(password, word))
+    }
+
+}
+
+/// Splits the given text into words, the way the library splits GECOS fields.
+pub(crate) fn split_words(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || ",;:.-_+@/".contains(c))
+        .filter(|w| !w.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns whether the word, or the reversed word, appears in the password, regardless of case.
+pub(crate) fn contains_word(password: &str, word: &str) -> bool {
+    let password = password.to_lowercase();
+    let word = word.to_lowercase();
+    let reversed: String = word.chars().rev().collect();
+    password.contains(&word) || password.contains(&reversed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use PWQuality;
+
+    use pwquality_sys::Error;
+
+    #[test]
+    fn birth_date_forms() {
+        let date = BirthDate::new(1985, 3, 12);
+        assert_eq!(date.forms(), ["1985", "1203", "0312", "120385", "031285", "850312", "12031985", "03121985", "19850312"]);
+        // the two digits year keeps its leading zero
+        assert!(BirthDate::new(2007, 11, 5).forms().contains(&"051107".to_owned()));
+    }
+
+    #[test]
+    fn gecos_words() {
+        assert_eq!(split_words("John Smith,Room 42,555-1234,,"), ["John", "Smith", "Room", "42", "555", "1234"]);
+        assert!(split_words(" ,;, ").is_empty());
+    }
+
+    #[test]
+    fn email_words() {
+        let mut user = UserContext::new("jsmith");
+        user.email = Some("john.smith@mail.example.info".to_owned());
+        // the top level domain is dropped
+        assert_eq!(user.forbidden_words(), ["example", "john", "john.smith", "mail", "smith"]);
+        user.email = Some("Administrator@LOCALHOST".to_owned());
+        assert_eq!(user.forbidden_words(), ["administrator", "localhost"]);
+    }
+
+    #[test]
+    fn forbidden_words() {
+        let mut user = UserContext::new("jsmith");
+        user.full_name = Some("Jo Smith-Brown".to_owned());
+        user.gecos = Some("Jo Smith,Bldg 7,,".to_owned());
+        user.birth_date = Some(BirthDate::new(1985, 3, 12));
+        user.org_words = vec!["Acme Corp".to_owned(), "R&D".to_owned()];
+        let words = user.forbidden_words();
+        // short words are left out, duplicates are removed
+        assert_eq!(words, ["0312", "03121985", "031285", "1203", "12031985", "120385", "1985", "19850312", "850312",
+                           "acme", "bldg", "brown", "corp", "smith"]);
+        // the username is left to the library
+        assert!(!words.contains(&"jsmith".to_owned()));
+        assert!(UserContext::default().forbidden_words().is_empty());
+    }
+
+    #[test]
+    fn reversed_words() {
+        assert!(contains_word("xxHTIMSxx", "smith"));
+        assert!(contains_word("xxSmithxx", "SMITH"));
+        assert!(!contains_word("xxSmitxx", "smith"));
+        let mut user = UserContext::new("jsmith");
+        user.full_name = Some("John Smith".to_owned());
+        assert_eq!(user.find_forbidden_word("42-nhoj-rocks"), Some("john".to_owned()));
+        assert_eq!(user.find_forbidden_word("42-jo-rocks"), None);
+    }
+
+    #[test]
+    fn check_with_context() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(8);
+        pwq.set_user_check(false);
+        let mut user = UserContext::new("jsmith");
+        user.org_words = vec!["Acme".to_owned()];
+        user.birth_date = Some(BirthDate::new(1985, 3, 12));
+        assert_eq!(pwq.check_with_context("Wq-7emca-Zt!x".to_owned(), None, &user), Err(Error::GecosCheck));
+        assert_eq!(pwq.check_with_context("Wq-7-1985-Zt!x".to_owned(), None, &user), Err(Error::GecosCheck));
+        assert!(pwq.check_with_context("Wq-7-Zt!x-Vb4".to_owned(), None, &user).is_ok());
+        let all = pwq.check_all_with_context("Wq-7acme-Zt!x".to_owned(), None, &user);
+        assert_eq!(all.violations(), &[Error::GecosCheck]);
+    }
+
+}
@@ -0,0 +1,105 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Enforcement of the checks for a system user, as done by the pam_pwquality module.
+//!
+//! The checks are skipped for users not listed in `/etc/passwd` when `local_users_only` is set.
+//! Otherwise they are run, but a rejection is not enforced when the password is changed by
+//! root (unless `enforce_for_root` is set) or when `enforcing` is not set: the module then only
+//! warns about the rejected password.
+
+use pwquality_sys::Error;
+
+/// Why a password was accepted without the checks being enforced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Bypass {
+    /// The user is not local and `local_users_only` is set, the checks were not run.
+    NonLocalUser,
+    /// The password is changed by root and `enforce_for_root` is not set.
+    Root,
+    /// The `enforcing` setting is not set.
+    NotEnforcing,
+}
+
+/// The result of checking the password of a system user.
+#[derive(Clone, Debug)]
+pub struct UserCheck {
+    result: Option<Result<i32, Error>>,
+    bypass: Option<Bypass>,
+}
+
+impl UserCheck {
+
+    pub(crate) fn skipped(bypass: Bypass) -> Self {
+        UserCheck {
+            result: None,
+            bypass: Some(bypass),
+        }
+    }
+
+    pub(crate) fn checked(result: Result<i32, Error>, bypass: Option<Bypass>) -> Self {
+        // enforcement is only bypassed for rejected passwords, not for failed checks
+        let bypass = match result {
+            Err(ref error) if error.is_rejection() => bypass,
+            _ => None,
+        };
+        UserCheck {
+            result: Some(result),
+            bypass,
+        }
+    }
+
+    /// Returns whether the password must be accepted, i.e. it passed the checks,
+    /// or their enforcement was bypassed.
+    pub fn is_accepted(&self) -> bool {
+        self.bypass.is_some() || self.result.as_ref().map(Result::is_ok).unwrap_or(false)
+    }
+
+    /// Returns the result of the checks, or `None` if they were not run.
+    pub fn result(&self) -> Option<&Result<i32, Error>> {
+        self.result.as_ref()
+    }
+
+    /// Returns the reason why the password was rejected, if it did not pass the checks,
+    /// even if the rejection was bypassed.
+    pub fn error(&self) -> Option<&Error> {
+        self.result.as_ref().and_then(|result| result.as_ref().err())
+    }
+
+    /// Returns why enforcement was bypassed, if the checks were skipped or the password
+    /// was rejected but must be accepted anyway.
+    pub fn bypass(&self) -> Option<Bypass> {
+        self.bypass
+    }
+
+    /// Returns whether enforcement was bypassed.
+    pub fn is_bypassed(&self) -> bool {
+        self.bypass.is_some()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_rejections_are_bypassed() {
+        let check = UserCheck::checked(Err(Error::MinLength), Some(Bypass::Root));
+        assert!(check.is_accepted());
+        assert_eq!(check.bypass(), Some(Bypass::Root));
+        let check = UserCheck::checked(Err(Error::Io { message: "permission denied".to_owned() }), Some(Bypass::Root));
+        assert!(!check.is_accepted());
+        assert_eq!(check.bypass(), None);
+        let check = UserCheck::checked(Err(Error::CfgfileOpen), Some(Bypass::NotEnforcing));
+        assert!(!check.is_accepted());
+        let check = UserCheck::checked(Ok(42), Some(Bypass::NotEnforcing));
+        assert!(check.is_accepted());
+        assert!(!check.is_bypassed());
+        let check = UserCheck::skipped(Bypass::NonLocalUser);
+        assert!(check.is_accepted());
+        assert_eq!(check.result(), None);
+        assert_eq!(check.error(), None);
+    }
+
+}
@@ -0,0 +1,771 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Estimation of the number of guesses needed to find a password.
+//!
+//! The estimation follows the approach of [zxcvbn](https://github.com/dropbox/zxcvbn):
+//! the password is matched against known patterns (dictionary words, possibly reversed
+//! or in l33t speak, keyboard walks, sequences, repeats and dates), each match is given
+//! a number of guesses, and the password is covered by the sequence of matches and
+//! brute forced segments that minimizes the total number of guesses.
+
+use keyboard::KeyboardLayout;
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_PASSWORD_LENGTH: usize = 100;
+const MAX_WORD_LENGTH: usize = 32;
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10000.0;
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+const MIN_YEAR_SPACE: f64 = 20.0;
+const MAX_SEQUENCE_DELTA: i64 = 5;
+const MAX_L33T_COMBINATIONS: usize = 32;
+
+const L33T_TABLE: &[(char, &[char])] = &[
+    ('a', &['4', '@']),
+    ('b', &['8']),
+    ('c', &['(', '{', '[', '<']),
+    ('e', &['3']),
+    ('g', &['6', '9']),
+    ('i', &['1', '!', '|']),
+    ('l', &['1', '|', '7']),
+    ('o', &['0']),
+    ('s', &['$', '5']),
+    ('t', &['+', '7']),
+    ('x', &['%']),
+    ('z', &['2']),
+];
+
+type Dictionary = Arc<HashMap<String, usize>>;
+
+// the dictionaries a password is matched against, borrowed from the estimator
+type Dictionaries<'a> = [&'a (String, Dictionary)];
+
+fn ranked(words: &str) -> Dictionary {
+    let mut dict = HashMap::new();
+    for (rank, word) in words.lines().map(str::trim).filter(|w| !w.is_empty()).enumerate() {
+        dict.entry(word.to_lowercase()).or_insert(rank + 1);
+    }
+    Arc::new(dict)
+}
+
+fn builtin_dictionaries() -> &'static [(String, Dictionary)] {
+    static BUILTIN: OnceLock<Vec<(String, Dictionary)>> = OnceLock::new();
+    BUILTIN.get_or_init(|| vec![
+        ("passwords".to_owned(), ranked(include_str!("../data/passwords.txt"))),
+        ("english".to_owned(), ranked(include_str!("../data/english.txt"))),
+    ])
+}
+
+/// The kind of pattern a part of a password matches.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Pattern {
+    /// A word of a dictionary, possibly reversed or with l33t substitutions.
+    Dictionary { word: String, dictionary: String, rank: usize, reversed: bool, l33t: bool },
+    /// A walk on adjacent keys of a keyboard.
+    Spatial { layout: &'static str, turns: usize, shifted: usize },
+    /// A sequence of characters with a constant difference of code point (e.g. `abcd` or `9753`).
+    Sequence { ascending: bool },
+    /// A repetition of a base string (e.g. `aaaa` or `abcabc`).
+    Repeat { base: String, count: usize },
+    /// A date or a year.
+    Date { year: i32, month: Option<u8>, day: Option<u8>, separator: bool },
+    /// Characters that match no known pattern.
+    BruteForce,
+}
+
+/// A part of a password matching a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    /// The index (in characters) of the first character of the match.
+    pub start: usize,
+    /// The index (in characters) after the last character of the match.
+    pub end: usize,
+    /// The matched part of the password.
+    pub token: String,
+    /// The matched pattern.
+    pub pattern: Pattern,
+    /// The estimated number of guesses needed to find this part.
+    pub guesses: f64,
+}
+
+/// Estimated times, in seconds, to crack a password in different attack scenarios.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrackTimes {
+    /// Online attack on a service limiting the number of attempts (100 per hour).
+    pub online_throttled: f64,
+    /// Online attack on a service without limit (10 per second).
+    pub online_unthrottled: f64,
+    /// Offline attack on passwords hashed with a slow function (10k per second).
+    pub offline_slow_hashing: f64,
+    /// Offline attack on passwords hashed with a fast function (10B per second).
+    pub offline_fast_hashing: f64,
+}
+
+/// The estimation of the strength of a password.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    guesses: f64,
+    patterns: Vec<Match>,
+}
+
+impl Estimate {
+
+    // an estimate without the patterns, as deserialized
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_guesses(guesses: f64) -> Self {
+        Estimate {
+            guesses,
+            patterns: Vec::new(),
+        }
+    }
+
+    /// Returns the estimated number of guesses needed to find the password.
+    pub fn guesses(&self) -> f64 {
+        self.guesses
+    }
+
+    /// Returns the entropy of the password in bits, i.e. the base 2 logarithm of the guesses.
+    pub fn log2_guesses(&self) -> f64 {
+        self.guesses.log2()
+    }
+
+    /// Returns the sequence of patterns covering the password.
+    pub fn patterns(&self) -> &[Match] {
+        &self.patterns
+    }
+
+    /// Returns the estimated times needed to crack the password.
+    pub fn crack_times(&self) -> CrackTimes {
+        CrackTimes {
+            online_throttled: self.guesses / (100.0 / 3600.0),
+            online_unthrottled: self.guesses / 10.0,
+            offline_slow_hashing: self.guesses / 1e4,
+            offline_fast_hashing: self.guesses / 1e10,
+        }
+    }
+
+}
+
+/// Estimates the number of guesses needed to find passwords.
+#[derive(Clone, Debug)]
+pub struct Estimator {
+    dictionaries: Vec<(String, Dictionary)>,
+    layouts: Vec<KeyboardLayout>,
+    reference_year: i32,
+}
+
+impl Default for Estimator {
+    fn default() -> Self {
+        Estimator::new()
+    }
+}
+
+impl Estimator {
+
+    /// Creates an estimator with the built-in dictionaries of common passwords and english words,
+    /// and the built-in keyboard layouts.
+    pub fn new() -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Estimator {
+            dictionaries: builtin_dictionaries().to_vec(),
+            layouts: KeyboardLayout::all(),
+            reference_year: 1970 + (now / 31_556_952) as i32,
+        }
+    }
+
+    /// Adds a dictionary of words, ordered from the most to the least frequent.
+    pub fn add_dictionary<S: Into<String>>(&mut self, name: S, words: Vec<String>) {
+        self.dictionaries.push((name.into(), ranked(&words.join("\n"))));
+    }
+
+    /// Sets the keyboard layouts on which walks are looked for.
+    pub fn set_layouts(&mut self, layouts: Vec<KeyboardLayout>) {
+        self.layouts = layouts;
+    }
+
+    /// Estimates the strength of a password.
+    /// The user inputs (username, name, email, …) are considered as a dictionary of
+    /// very likely words. Only the first 100 characters of the password are considered,
+    /// as the estimation gets expensive on long inputs.
+    pub fn estimate(&self, password: &str, user_inputs: &[&str]) -> Estimate {
+        let password: String = password.chars().take(MAX_PASSWORD_LENGTH).collect();
+        let user_dictionary;
+        let mut dictionaries: Vec<&(String, Dictionary)> = self.dictionaries.iter().collect();
+        if !user_inputs.is_empty() {
+            user_dictionary = ("user_inputs".to_owned(), ranked(&user_inputs.join("\n")));
+            dictionaries.push(&user_dictionary);
+        }
+        self.estimate_with(&password, &dictionaries)
+    }
+
+    fn estimate_with(&self, password: &str, dictionaries: &Dictionaries) -> Estimate {
+        let chars: Vec<char> = password.chars().collect();
+        let mut matches = Vec::new();
+        matches.extend(dictionary_matches(&chars, dictionaries));
+        matches.extend(reversed_dictionary_matches(&chars, dictionaries));
+        matches.extend(l33t_matches(&chars, dictionaries));
+        for layout in &self.layouts {
+            matches.extend(spatial_matches(&chars, layout));
+        }
+        matches.extend(sequence_matches(&chars));
+        matches.extend(self.repeat_matches(&chars, dictionaries));
+        matches.extend(date_matches(&chars));
+        for m in &mut matches {
+            m.guesses = self.guesses(m, chars.len());
+        }
+        self.most_guessable_sequence(&chars, matches)
+    }
+
+    fn guesses(&self, m: &Match, password_len: usize) -> f64 {
+        let len = m.end - m.start;
+        let min = if len < password_len {
+            if len == 1 { MIN_SUBMATCH_GUESSES_SINGLE_CHAR } else { MIN_SUBMATCH_GUESSES_MULTI_CHAR }
+        } else {
+            1.0
+        };
+        let guesses = match m.pattern {
+            Pattern::Dictionary { rank, reversed, l33t, .. } => {
+                let mut guesses = rank as f64 * uppercase_variations(&m.token);
+                if l33t {
+                    guesses *= l33t_variations(&m.token);
+                }
+                if reversed {
+                    guesses *= 2.0;
+                }
+                guesses
+            },
+            Pattern::Spatial { layout, turns, shifted } => {
+                let layout = self.layouts.iter().find(|l| l.name() == layout);
+                let (keys, degree) = layout.map(|l| (l.keys() as f64, l.average_degree())).unwrap_or((94.0, 4.0));
+                let mut guesses = 0.0;
+                for i in 2..=len {
+                    for j in 1..=turns.min(i - 1) {
+                        guesses += binomial(i - 1, j - 1) * keys * degree.powi(j as i32);
+                    }
+                }
+                if shifted > 0 {
+                    let unshifted = len - shifted;
+                    if unshifted == 0 {
+                        guesses *= 2.0;
+                    } else {
+                        guesses *= (1..=shifted.min(unshifted)).map(|i| binomial(shifted + unshifted, i)).sum::<f64>();
+                    }
+                }
+                guesses
+            },
+            Pattern::Sequence { ascending } => {
+                let first = m.token.chars().next().unwrap_or('a');
+                let mut base = if "aAzZ019".contains(first) {
+                    4.0
+                } else if first.is_ascii_digit() {
+                    10.0
+                } else {
+                    26.0
+                };
+                if !ascending {
+                    base *= 2.0;
+                }
+                base * len as f64
+            },
+            Pattern::Repeat { count, .. } => m.guesses * count as f64,
+            Pattern::Date { year, month, separator, .. } => {
+                let space = f64::from((year - self.reference_year).abs()).max(MIN_YEAR_SPACE);
+                let mut guesses = if month.is_some() { space * 365.0 } else { space };
+                if separator {
+                    guesses *= 4.0;
+                }
+                guesses
+            },
+            Pattern::BruteForce => BRUTEFORCE_CARDINALITY.powi(len as i32).min(f64::MAX),
+        };
+        guesses.max(min)
+    }
+
+    fn repeat_matches(&self, chars: &[char], dictionaries: &Dictionaries) -> Vec<Match> {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start < chars.len() {
+            let mut best: Option<(usize, usize)> = None;
+            for base_len in 1..=(chars.len() - start) / 2 {
+                let base = &chars[start..start + base_len];
+                let mut count = 1;
+                while start + (count + 1) * base_len <= chars.len()
+                    && &chars[start + count * base_len..start + (count + 1) * base_len] == base {
+                    count += 1;
+                }
+                if count > 1 && best.map(|(l, c)| base_len * count > l * c).unwrap_or(true) {
+                    best = Some((base_len, count));
+                }
+            }
+            match best {
+                Some((base_len, count)) => {
+                    let end = start + base_len * count;
+                    let base: String = chars[start..start + base_len].iter().collect();
+                    let base_guesses = self.estimate_with(&base, dictionaries).guesses;
+                    matches.push(Match {
+                        start,
+                        end,
+                        token: chars[start..end].iter().collect(),
+                        pattern: Pattern::Repeat { base, count },
+                        // the guesses of the base, multiplied by the count afterwards
+                        guesses: base_guesses,
+                    });
+                    start = end;
+                },
+                None => start += 1,
+            }
+        }
+        matches
+    }
+
+    fn most_guessable_sequence(&self, chars: &[char], matches: Vec<Match>) -> Estimate {
+        let n = chars.len();
+        if n == 0 {
+            return Estimate { guesses: 1.0, patterns: vec![] };
+        }
+        let bruteforce = |start: usize, end: usize| {
+            let mut m = Match {
+                start,
+                end,
+                token: chars[start..end].iter().collect(),
+                pattern: Pattern::BruteForce,
+                guesses: 0.0,
+            };
+            m.guesses = self.guesses(&m, n);
+            m
+        };
+        // for each end position and sequence length, the best last match, the product
+        // of the guesses of the sequence and the total guesses
+        let mut optimal: Vec<HashMap<usize, (Match, f64, f64)>> = vec![HashMap::new(); n];
+        fn update(optimal: &mut [HashMap<usize, (Match, f64, f64)>], m: Match, len: usize) {
+            let k = m.end - 1;
+            let pi = if m.start > 0 {
+                optimal[m.start - 1].get(&(len - 1)).map(|&(_, pi, _)| pi).unwrap_or(1.0) * m.guesses
+            } else {
+                m.guesses
+            };
+            let g = factorial(len) * pi + MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(len as i32 - 1);
+            let dominated = optimal[k].iter().any(|(&l, &(_, _, competing))| l <= len && competing <= g);
+            if !dominated {
+                optimal[k].insert(len, (m, pi, g));
+            }
+        }
+        let mut by_end: Vec<Vec<Match>> = vec![Vec::new(); n];
+        for m in matches {
+            by_end[m.end - 1].push(m);
+        }
+        for (k, ending) in by_end.iter().enumerate() {
+            for m in ending {
+                if m.start > 0 {
+                    let lens: Vec<usize> = optimal[m.start - 1].keys().cloned().collect();
+                    for l in lens {
+                        update(&mut optimal, m.clone(), l + 1);
+                    }
+                } else {
+                    update(&mut optimal, m.clone(), 1);
+                }
+            }
+            update(&mut optimal, bruteforce(0, k + 1), 1);
+            for i in 1..=k {
+                let lens: Vec<(usize, bool)> = optimal[i - 1].iter()
+                    .map(|(&l, (m, _, _))| (l, m.pattern == Pattern::BruteForce))
+                    .collect();
+                for (l, last_is_bruteforce) in lens {
+                    // two consecutive brute forced segments are never better than one
+                    if !last_is_bruteforce {
+                        update(&mut optimal, bruteforce(i, k + 1), l + 1);
+                    }
+                }
+            }
+        }
+        let (mut len, guesses) = optimal[n - 1].iter()
+            .map(|(&l, &(_, _, g))| (l, g))
+            .fold((0, f64::INFINITY), |best, cur| if cur.1 < best.1 { cur } else { best });
+        let mut patterns = Vec::new();
+        let mut k = n;
+        while k > 0 && len > 0 {
+            let m = optimal[k - 1][&len].0.clone();
+            k = m.start;
+            len -= 1;
+            patterns.push(m);
+        }
+        patterns.reverse();
+        Estimate { guesses, patterns }
+    }
+
+}
+
+fn factorial(n: usize) -> f64 {
+    (1..=n).map(|i| i as f64).product()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (1..=k).fold(1.0, |acc, i| acc * (n + 1 - i) as f64 / i as f64)
+}
+
+fn uppercase_variations(token: &str) -> f64 {
+    let upper = token.chars().filter(|c| c.is_uppercase()).count();
+    let lower = token.chars().filter(|c| c.is_lowercase()).count();
+    if upper == 0 || token.to_lowercase() == token {
+        return 1.0;
+    }
+    let first_upper = token.chars().next().map(char::is_uppercase).unwrap_or(false);
+    let last_upper = token.chars().last().map(char::is_uppercase).unwrap_or(false);
+    if lower == 0 || (upper == 1 && (first_upper || last_upper)) {
+        return 2.0;
+    }
+    (1..=upper.min(lower)).map(|i| binomial(upper + lower, i)).sum()
+}
+
+fn l33t_variations(token: &str) -> f64 {
+    let lower = token.to_lowercase();
+    let mut variations = 1.0;
+    for &(letter, subs) in L33T_TABLE {
+        for &sub in subs {
+            let subbed = lower.chars().filter(|&c| c == sub).count();
+            let unsubbed = lower.chars().filter(|&c| c == letter).count();
+            if subbed == 0 {
+                continue;
+            }
+            if unsubbed == 0 {
+                variations *= 2.0;
+            } else {
+                variations *= (1..=subbed.min(unsubbed)).map(|i| binomial(subbed + unsubbed, i)).sum::<f64>();
+            }
+        }
+    }
+    variations
+}
+
+fn dictionary_matches(chars: &[char], dictionaries: &Dictionaries) -> Vec<Match> {
+    let lower: Vec<String> = chars.iter().map(|c| c.to_lowercase().collect()).collect();
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        let mut word = String::new();
+        for end in start + 1..=chars.len().min(start + MAX_WORD_LENGTH) {
+            word.push_str(&lower[end - 1]);
+            for (name, dict) in dictionaries {
+                if let Some(&rank) = dict.get(&word) {
+                    matches.push(Match {
+                        start,
+                        end,
+                        token: chars[start..end].iter().collect(),
+                        pattern: Pattern::Dictionary {
+                            word: word.clone(),
+                            dictionary: name.clone(),
+                            rank,
+                            reversed: false,
+                            l33t: false,
+                        },
+                        guesses: 0.0,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+fn reversed_dictionary_matches(chars: &[char], dictionaries: &Dictionaries) -> Vec<Match> {
+    let reversed: Vec<char> = chars.iter().rev().cloned().collect();
+    let n = chars.len();
+    dictionary_matches(&reversed, dictionaries).into_iter()
+        // palindromes are already matched forward
+        .filter(|m| m.token.chars().rev().collect::<String>() != m.token)
+        .map(|mut m| {
+            let (start, end) = (n - m.end, n - m.start);
+            m.start = start;
+            m.end = end;
+            m.token = chars[start..end].iter().collect();
+            if let Pattern::Dictionary { ref mut reversed, .. } = m.pattern {
+                *reversed = true;
+            }
+            m
+        })
+        .collect()
+}
+
+fn l33t_matches(chars: &[char], dictionaries: &Dictionaries) -> Vec<Match> {
+    // the possible letters for each substitution character present in the password
+    let mut candidates: Vec<(char, Vec<char>)> = Vec::new();
+    for &c in chars {
+        if candidates.iter().any(|&(sub, _)| sub == c) {
+            continue;
+        }
+        let letters: Vec<char> = L33T_TABLE.iter().filter(|&&(_, subs)| subs.contains(&c)).map(|&(l, _)| l).collect();
+        if !letters.is_empty() {
+            candidates.push((c, letters));
+        }
+    }
+    if candidates.is_empty() {
+        return vec![];
+    }
+    let mut combinations: Vec<Vec<(char, char)>> = vec![vec![]];
+    for &(sub, ref letters) in &candidates {
+        let mut next = Vec::new();
+        for combination in &combinations {
+            for &letter in letters {
+                let mut combination = combination.clone();
+                combination.push((sub, letter));
+                next.push(combination);
+            }
+        }
+        next.truncate(MAX_L33T_COMBINATIONS);
+        combinations = next;
+    }
+    let mut matches: Vec<Match> = Vec::new();
+    for combination in combinations {
+        let translated: Vec<char> = chars.iter()
+            .map(|c| combination.iter().find(|&&(sub, _)| sub == *c).map(|&(_, l)| l).unwrap_or(*c))
+            .collect();
+        for mut m in dictionary_matches(&translated, dictionaries) {
+            let token: Vec<char> = chars[m.start..m.end].to_vec();
+            if token.len() < 2 || !token.iter().any(|c| combination.iter().any(|&(sub, _)| sub == *c)) {
+                continue;
+            }
+            m.token = token.into_iter().collect();
+            if let Pattern::Dictionary { ref mut l33t, .. } = m.pattern {
+                *l33t = true;
+            }
+            if !matches.contains(&m) {
+                matches.push(m);
+            }
+        }
+    }
+    matches
+}
+
+fn spatial_matches(chars: &[char], layout: &KeyboardLayout) -> Vec<Match> {
+    let password: String = chars.iter().collect();
+    layout.walks(&password).into_iter()
+        // two adjacent keys are too common to be a pattern
+        .filter(|walk| walk.len() > 2)
+        .map(|walk| Match {
+            start: walk.start,
+            end: walk.end,
+            token: chars[walk.start..walk.end].iter().collect(),
+            pattern: Pattern::Spatial { layout: walk.layout, turns: walk.turns, shifted: walk.shifted },
+            guesses: 0.0,
+        })
+        .collect()
+}
+
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    if chars.len() < 2 {
+        return matches;
+    }
+    let mut push = |start: usize, last: usize, delta: i64| {
+        if (last - start > 1 || delta.abs() == 1) && delta != 0 && delta.abs() <= MAX_SEQUENCE_DELTA {
+            matches.push(Match {
+                start,
+                end: last + 1,
+                token: chars[start..=last].iter().collect(),
+                pattern: Pattern::Sequence { ascending: delta > 0 },
+                guesses: 0.0,
+            });
+        }
+    };
+    let mut start = 0;
+    let mut last_delta = None;
+    for k in 1..chars.len() {
+        let delta = chars[k] as i64 - chars[k - 1] as i64;
+        match last_delta {
+            None => last_delta = Some(delta),
+            Some(last) if last == delta => (),
+            Some(last) => {
+                push(start, k - 1, last);
+                start = k - 1;
+                last_delta = Some(delta);
+            }
+        }
+    }
+    push(start, chars.len() - 1, last_delta.unwrap_or(0));
+    matches
+}
+
+// splits of a date without separator into day, month and year, as (first cut, second cut)
+fn date_splits(len: usize) -> &'static [(usize, usize)] {
+    match len {
+        4 => &[(1, 2), (2, 3)],
+        5 => &[(1, 3), (2, 3)],
+        6 => &[(1, 2), (2, 4), (4, 5)],
+        7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+        8 => &[(2, 4), (4, 6)],
+        _ => &[],
+    }
+}
+
+fn two_digits_year(year: i32) -> i32 {
+    if year > 50 { 1900 + year } else { 2000 + year }
+}
+
+// interprets three integers as a date, with the year either first or last
+fn to_date(ints: [i32; 3], year_digits: (usize, usize)) -> Option<(i32, u8, u8)> {
+    if ints[1] < 1 || ints[1] > 31 {
+        return None;
+    }
+    // day and month come before a trailing year, and after a leading year
+    let candidates = [(ints[2], year_digits.1, ints[0], ints[1], false), (ints[0], year_digits.0, ints[1], ints[2], true)];
+    for &(year, digits, a, b, year_first) in &candidates {
+        let year = match digits {
+            4 if (1000..=2050).contains(&year) => year,
+            1 | 2 => two_digits_year(year),
+            _ => continue,
+        };
+        let (first, second) = if year_first { ((a, b), (b, a)) } else { ((b, a), (a, b)) };
+        for &(month, day) in &[first, second] {
+            if (1..=12).contains(&month) && (1..=31).contains(&day) {
+                return Some((year, month as u8, day as u8));
+            }
+        }
+    }
+    None
+}
+
+fn parse(chars: &[char]) -> i32 {
+    chars.iter().fold(0, |acc, c| acc * 10 + c.to_digit(10).unwrap_or(0) as i32)
+}
+
+fn date_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let n = chars.len();
+    for start in 0..n {
+        for end in start + 4..=n.min(start + 10) {
+            let token = &chars[start..end];
+            let date = if token.iter().all(|c| c.is_ascii_digit()) {
+                if token.len() == 4 && (token[0] == '1' && token[1] == '9' || token[0] == '2' && token[1] == '0') {
+                    matches.push(Match {
+                        start,
+                        end,
+                        token: token.iter().collect(),
+                        pattern: Pattern::Date { year: parse(token), month: None, day: None, separator: false },
+                        guesses: 0.0,
+                    });
+                }
+                date_splits(token.len()).iter()
+                    .filter_map(|&(i, j)| {
+                        let ints = [parse(&token[..i]), parse(&token[i..j]), parse(&token[j..])];
+                        to_date(ints, (i, token.len() - j))
+                    })
+                    .next()
+                    .map(|date| (date, false))
+            } else if token.len() >= 6 {
+                let parts: Vec<&[char]> = token.split(|c| !c.is_ascii_digit()).collect();
+                let separators: Vec<char> = token.iter().cloned().filter(|c| !c.is_ascii_digit()).collect();
+                if parts.len() == 3 && separators[0] == separators[1] && " /\\_.-".contains(separators[0])
+                    && parts.iter().all(|p| !p.is_empty() && p.len() <= 4) && parts[1].len() <= 2 {
+                    let ints = [parse(parts[0]), parse(parts[1]), parse(parts[2])];
+                    to_date(ints, (parts[0].len(), parts[2].len())).map(|date| (date, true))
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+            if let Some(((year, month, day), separator)) = date {
+                matches.push(Match {
+                    start,
+                    end,
+                    token: token.iter().collect(),
+                    pattern: Pattern::Date { year, month: Some(month), day: Some(day), separator },
+                    guesses: 0.0,
+                });
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(estimate: &Estimate) -> Vec<&Pattern> {
+        estimate.patterns().iter().map(|m| &m.pattern).collect()
+    }
+
+    #[test]
+    fn dictionary() {
+        let estimator = Estimator::new();
+        let estimate = estimator.estimate("password", &[]);
+        match *patterns(&estimate)[0] {
+            Pattern::Dictionary { ref word, reversed, l33t, .. } => {
+                assert_eq!(word, "password");
+                assert!(!reversed && !l33t);
+            },
+            ref pattern => panic!("unexpected pattern {:?}", pattern),
+        }
+        assert!(estimate.guesses() < 100.0);
+
+        let estimate = estimator.estimate("p4ssw0rd", &[]);
+        assert!(patterns(&estimate).iter().any(|p| match **p {
+            Pattern::Dictionary { l33t, .. } => l33t,
+            _ => false,
+        }));
+
+        let estimate = estimator.estimate("drowssap", &[]);
+        assert!(patterns(&estimate).iter().any(|p| match **p {
+            Pattern::Dictionary { reversed, .. } => reversed,
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn user_inputs() {
+        let estimator = Estimator::new();
+        let without = estimator.estimate("zorglub1984", &[]);
+        let with = estimator.estimate("zorglub1984", &["zorglub"]);
+        assert!(with.guesses() < without.guesses());
+        assert!(patterns(&with).iter().any(|p| match **p {
+            Pattern::Dictionary { ref dictionary, .. } => dictionary == "user_inputs",
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn sequences_repeats_and_dates() {
+        let estimator = Estimator::new();
+        assert_eq!(patterns(&estimator.estimate("abcdefgh", &[])), vec![&Pattern::Sequence { ascending: true }]);
+        match *patterns(&estimator.estimate("xyzxyzxyz", &[]))[0] {
+            Pattern::Repeat { ref base, count } => {
+                assert_eq!(base, "xyz");
+                assert_eq!(count, 3);
+            },
+            ref pattern => panic!("unexpected pattern {:?}", pattern),
+        }
+        match *patterns(&estimator.estimate("13/05/1987", &[]))[0] {
+            Pattern::Date { year, month, day, separator } => {
+                assert_eq!((year, month, day, separator), (1987, Some(5), Some(13), true));
+            },
+            ref pattern => panic!("unexpected pattern {:?}", pattern),
+        }
+    }
+
+    #[test]
+    fn random_is_stronger() {
+        let estimator = Estimator::new();
+        let weak = estimator.estimate("qwerty123", &[]);
+        let strong = estimator.estimate("vT8#qL2!mZ9@", &[]);
+        assert!(strong.guesses() > weak.guesses() * 1e6);
+        assert!(strong.log2_guesses() > 30.0);
+        assert_eq!(estimator.estimate("", &[]).guesses(), 1.0);
+    }
+
+    #[test]
+    fn long_passwords_are_truncated() {
+        let estimator = Estimator::new();
+        let prefix: String = (0..MAX_PASSWORD_LENGTH).map(|i| (b'a' + (i * 7 % 26) as u8) as char).collect();
+        let long = format!("{}{}", prefix, "x9!".repeat(5000));
+        assert_eq!(estimator.estimate(&long, &[]), estimator.estimate(&prefix, &[]));
+    }
+
+}
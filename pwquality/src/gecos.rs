@@ -0,0 +1,269 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Sources of the GECOS field of users.
+//!
+//! The GECOS check of the library looks the user up in the system password database,
+//! so its result depends on the host. A `GecosProvider` set with
+//! `PWQuality::set_gecos_provider` gives the GECOS field from another source, and the
+//! same rule is applied: any word longer than 3 characters of the field may not appear in
+//! the password, forward or reversed, regardless of case.
+//! The field is only checked when the GECOS check is enabled with `PWQuality::set_gecos_check`,
+//! and the lookup of the library in the system password database is then skipped.
+
+use context::{contains_word, split_words};
+use users;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// A source of the GECOS field of users.
+pub trait GecosProvider {
+
+    /// Returns the GECOS field of the user with the given name, if it is known.
+    fn gecos(&self, username: &str) -> Option<String>;
+
+}
+
+impl<F> GecosProvider for F where F: Fn(&str) -> Option<String> {
+    fn gecos(&self, username: &str) -> Option<String> {
+        self(username)
+    }
+}
+
+/// The GECOS fields of the system password database, as used by the library.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PasswdGecos;
+
+impl GecosProvider for PasswdGecos {
+    fn gecos(&self, username: &str) -> Option<String> {
+        users::getpwnam(username).ok().flatten().map(|passwd| passwd.gecos)
+    }
+}
+
+/// GECOS fields given in memory, per username.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryGecos {
+    fields: HashMap<String, String>,
+}
+
+impl MemoryGecos {
+
+    /// Creates an empty source.
+    pub fn new() -> Self {
+        MemoryGecos::default()
+    }
+
+    /// Sets the GECOS field of the user with the given name.
+    pub fn insert<U: Into<String>, G: Into<String>>(&mut self, username: U, gecos: G) {
+        self.fields.insert(username.into(), gecos.into());
+    }
+
+}
+
+impl GecosProvider for MemoryGecos {
+    fn gecos(&self, username: &str) -> Option<String> {
+        self.fields.get(username).cloned()
+    }
+}
+
+/// GECOS fields read from an LDIF export of an LDAP directory.
+///
+/// The users are identified by their `uid` attribute, and their GECOS field is the
+/// `gecos` attribute, or the `cn` attribute for entries without one.
+#[derive(Clone, Debug, Default)]
+pub struct LdifGecos {
+    fields: MemoryGecos,
+}
+
+impl LdifGecos {
+
+    /// Reads the entries of the given LDIF file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        LdifGecos::parse(&content)
+    }
+
+    /// Reads the entries of the given LDIF content.
+    /// An error of kind `InvalidData` is returned if a line is not an attribute.
+    pub fn parse(content: &str) -> io::Result<Self> {
+        let mut fields = MemoryGecos::new();
+        for entry in unfold(content).split(|line| line.is_empty()) {
+            let mut uids = Vec::new();
+            let mut gecos = None;
+            let mut cn = None;
+            for line in entry.iter().filter(|line| !line.starts_with('#')) {
+                let (name, value) = attribute(line)?;
+                match name.as_str() {
+                    "uid" => uids.push(value),
+                    "gecos" if gecos.is_none() => gecos = Some(value),
+                    "cn" if cn.is_none() => cn = Some(value),
+                    _ => (),
+                }
+            }
+            if let Some(field) = gecos.or(cn) {
+                for uid in uids {
+                    fields.insert(uid, field.clone());
+                }
+            }
+        }
+        Ok(LdifGecos { fields })
+    }
+
+}
+
+impl GecosProvider for LdifGecos {
+    fn gecos(&self, username: &str) -> Option<String> {
+        self.fields.gecos(username)
+    }
+}
+
+// joins the continuation lines, starting with a space, to the previous line
+fn unfold(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in content.lines() {
+        let line = line.trim_end_matches('\r');
+        match lines.last_mut() {
+            Some(last) if line.starts_with(' ') && !last.is_empty() => last.push_str(&line[1..]),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+// returns the lowercase name, without options, and the value of an attribute line
+fn attribute(line: &str) -> io::Result<(String, String)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("invalid LDIF line: {}", line));
+    let idx = line.find(':').ok_or_else(invalid)?;
+    let name = line[..idx].split(';').next().unwrap_or("").trim().to_lowercase();
+    let rest = &line[idx + 1..];
+    let value = if let Some(encoded) = rest.strip_prefix(':') {
+        let bytes = decode_base64(encoded.trim()).ok_or_else(invalid)?;
+        String::from_utf8(bytes).map_err(|_| invalid())?
+    } else {
+        rest.trim().to_owned()
+    };
+    Ok((name, value))
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in text.bytes().filter(|&c| c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        acc = (acc << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Returns the first word longer than 3 characters of the GECOS field found in the
+/// password, in lowercase, if any.
+pub fn find_gecos_word(password: &str, gecos: &str) -> Option<String> {
+    split_words(gecos).into_iter()
+        .filter(|word| word.chars().count() > 3)
+        .map(|word| word.to_lowercase())
+        .find(|word| contains_word(password, word))
+}
+
+/// The provider set on an instance.
+#[derive(Default)]
+pub(crate) struct Provider(pub(crate) Option<Box<dyn GecosProvider>>);
+
+impl fmt::Debug for Provider {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Provider({})", if self.0.is_some() { "Some" } else { "None" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use PWQuality;
+
+    use pwquality_sys::Error;
+
+    const LDIF: &str = "\
+version: 1
+
+# John Doe
+dn: uid=jdoe,ou=people,dc=example,dc=com
+objectClass: posixAccount
+uid: jdoe
+cn: John Doe
+gecos: John Doe,Room 42,
+ Building Zorglub
+
+dn: uid=adupont,ou=people,dc=example,dc=com
+uid: adupont
+cn;lang-fr:: QW5kcsOpIER1cG9udA==
+
+dn: ou=people,dc=example,dc=com
+ou: people
+";
+
+    #[test]
+    fn ldif() {
+        let ldif = LdifGecos::parse(LDIF).unwrap();
+        assert_eq!(ldif.gecos("jdoe"), Some("John Doe,Room 42,Building Zorglub".to_owned()));
+        assert_eq!(ldif.gecos("adupont"), Some("André Dupont".to_owned()));
+        assert_eq!(ldif.gecos("people"), None);
+        assert!(LdifGecos::parse("uid: jdoe\nnot an attribute\n").is_err());
+        assert!(LdifGecos::parse("uid: jdoe\ncn:: not base64!\n").is_err());
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert_eq!(decode_base64("Zg==").unwrap(), b"f");
+        assert_eq!(decode_base64("Zm8=").unwrap(), b"fo");
+        assert_eq!(decode_base64("Zm9v").unwrap(), b"foo");
+        assert_eq!(decode_base64("Zm9vYmFy").unwrap(), b"foobar");
+        assert_eq!(decode_base64("Zm9v*"), None);
+    }
+
+    #[test]
+    fn gecos_words() {
+        let gecos = "John Doe,Room 42,Building Zorglub";
+        assert_eq!(find_gecos_word("xx-BUILDING-xx", gecos), Some("building".to_owned()));
+        assert_eq!(find_gecos_word("bulgroz!", gecos), Some("zorglub".to_owned()));
+        // words of 3 characters or less are allowed
+        assert_eq!(find_gecos_word("john-doe-42", gecos), Some("john".to_owned()));
+        assert_eq!(find_gecos_word("doe-42-jon", gecos), None);
+    }
+
+    #[test]
+    fn provider() {
+        let pwq = PWQuality::new();
+        let mut fields = MemoryGecos::new();
+        fields.insert("jdoe", "John Doe,Quantum Team");
+        pwq.set_gecos_provider(fields);
+        let check = |password: &str| pwq.check(password.to_owned(), None, Some("jdoe".to_owned()));
+
+        pwq.set_gecos_check(false);
+        assert!(check("x7#Quantum#Vr2").is_ok());
+        pwq.set_gecos_check(true);
+        assert_eq!(check("x7#Quantum#Vr2"), Err(Error::GecosCheck));
+        assert!(check("x7#Qvntvm#Vr2").is_ok());
+        // the setting is restored after the library check
+        assert!(pwq.get_gecos_check());
+    }
+
+}
@@ -0,0 +1,713 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Generation of passwords following an explicit specification.
+//!
+//! A `GeneratorSpec` describes the characters allowed in a password, the classes of
+//! characters it must contain, its length range, and optionally a template giving
+//! the class of each character. Templates use the following placeholders, any other
+//! character being copied as is (use `\` to escape a placeholder):
+//!
+//!  - `c`: lowercase consonant, `C`: uppercase consonant;
+//!  - `v`: lowercase vowel, `V`: uppercase vowel;
+//!  - `l`: lowercase letter, `L`: uppercase letter, `a`: any letter;
+//!  - `9`: digit;
+//!  - `s`: symbol (any other character);
+//!  - `*`: any allowed character.
+//!
+//! For example `Cvccvc-99` generates passwords such as `Bivtar-42`.
+//! The classes required by the specification and the settings are placed in the
+//! placeholders able to produce them.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use pwquality_sys::Error;
+
+use requirements::Requirements;
+
+/// The class of a character, as considered by the library.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    /// Digits: `[0-9]`.
+    Digit,
+    /// Lowercase letters: `[a-z]`.
+    Lowercase,
+    /// Uppercase letters: `[A-Z]`.
+    Uppercase,
+    /// Any other character.
+    Other,
+}
+
+impl CharClass {
+
+    /// Returns the class of the given character.
+    pub fn of(c: char) -> Self {
+        if c.is_ascii_digit() {
+            CharClass::Digit
+        } else if c.is_ascii_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_ascii_uppercase() {
+            CharClass::Uppercase
+        } else {
+            CharClass::Other
+        }
+    }
+
+}
+
+/// The default alphabet: printable ASCII characters, except space.
+pub const DEFAULT_ALPHABET: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+const VOWELS: &str = "aeiouyAEIOUY";
+
+/// The specification of generated passwords.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratorSpec {
+    /// The characters allowed in the password.
+    pub alphabet: String,
+    /// The characters never used, even if they are in the alphabet.
+    pub forbidden: String,
+    /// The classes of characters the password must contain at least once.
+    pub required_classes: Vec<CharClass>,
+    /// The minimum length of the password.
+    pub min_length: usize,
+    /// The maximum length of the password.
+    pub max_length: usize,
+    /// If set, the first character of the password is of one of these classes.
+    pub first: Option<Vec<CharClass>>,
+    /// If set, the template the password follows. The length constraints are then
+    /// ignored, and the first character constraint restricts the first placeholder.
+    pub template: Option<String>,
+    /// The maximum number of passwords generated before giving up when they do not
+    /// pass the checks.
+    pub max_attempts: usize,
+}
+
+impl Default for GeneratorSpec {
+    fn default() -> Self {
+        GeneratorSpec {
+            alphabet: DEFAULT_ALPHABET.to_owned(),
+            forbidden: String::new(),
+            required_classes: vec![],
+            min_length: 12,
+            max_length: 16,
+            first: None,
+            template: None,
+            max_attempts: 100,
+        }
+    }
+}
+
+// an element of a parsed template
+#[derive(Clone, Debug)]
+enum Slot {
+    Literal(char),
+    OneOf(Vec<char>),
+}
+
+impl Slot {
+
+    fn produces(&self, class: CharClass) -> bool {
+        match *self {
+            Slot::Literal(c) => CharClass::of(c) == class,
+            Slot::OneOf(ref candidates) => candidates.iter().any(|&c| CharClass::of(c) == class),
+        }
+    }
+
+}
+
+// tries to give the mandatory class `i` a slot, moving the classes already placed if needed
+fn place(i: usize, classes: &[CharClass], slots: &[Slot], order: &[usize], owners: &mut [Option<usize>], seen: &mut [bool]) -> bool {
+    for &s in order {
+        if seen[s] || !matches!(slots[s], Slot::OneOf(_)) || !slots[s].produces(classes[i]) {
+            continue;
+        }
+        seen[s] = true;
+        if owners[s].is_none_or(|j| place(j, classes, slots, order, owners, seen)) {
+            owners[s] = Some(i);
+            return true;
+        }
+    }
+    false
+}
+
+fn unsatisfiable<S: Into<String>>(reason: S) -> Error {
+    Error::Unsatisfiable { reason: reason.into() }
+}
+
+impl GeneratorSpec {
+
+    /// Creates a specification generating passwords following the given template,
+    /// with characters from the default alphabet.
+    pub fn from_template<S: Into<String>>(template: S) -> Self {
+        GeneratorSpec {
+            template: Some(template.into()),
+            ..Default::default()
+        }
+    }
+
+    // the allowed characters, without the forbidden ones
+    fn allowed(&self) -> Vec<char> {
+        let mut allowed: Vec<char> = self.alphabet.chars()
+            .filter(|c| !self.forbidden.contains(*c) && !c.is_control())
+            .collect();
+        allowed.sort();
+        allowed.dedup();
+        allowed
+    }
+
+    fn parse_template(&self, template: &str, allowed: &[char]) -> Result<Vec<Slot>, Error> {
+        let select = |f: &dyn Fn(char) -> bool| -> Vec<char> { allowed.iter().cloned().filter(|&c| f(c)).collect() };
+        let mut slots = Vec::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            let candidates = match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => {
+                        slots.push(Slot::Literal(escaped));
+                        continue;
+                    },
+                    None => return Err(unsatisfiable("template ends with an escape character")),
+                },
+                'c' => select(&|c| c.is_ascii_lowercase() && !VOWELS.contains(c)),
+                'C' => select(&|c| c.is_ascii_uppercase() && !VOWELS.contains(c)),
+                'v' => select(&|c| c.is_ascii_lowercase() && VOWELS.contains(c)),
+                'V' => select(&|c| c.is_ascii_uppercase() && VOWELS.contains(c)),
+                'l' => select(&|c| c.is_ascii_lowercase()),
+                'L' => select(&|c| c.is_ascii_uppercase()),
+                'a' => select(&|c| c.is_ascii_alphabetic()),
+                '9' => select(&|c| c.is_ascii_digit()),
+                's' => select(&|c| CharClass::of(c) == CharClass::Other),
+                '*' => allowed.to_vec(),
+                _ => {
+                    slots.push(Slot::Literal(c));
+                    continue;
+                },
+            };
+            if candidates.is_empty() {
+                return Err(unsatisfiable(format!("no allowed character for placeholder `{}`", c)));
+            }
+            slots.push(Slot::OneOf(candidates));
+        }
+        if slots.is_empty() {
+            return Err(unsatisfiable("empty template"));
+        }
+        if let Some(ref first) = self.first {
+            let allowed = |c: char| first.contains(&CharClass::of(c));
+            match slots[0] {
+                Slot::Literal(c) if allowed(c) => (),
+                Slot::Literal(_) => return Err(unsatisfiable("the template starts with a character of a class not allowed first")),
+                Slot::OneOf(ref mut candidates) => candidates.retain(|&c| allowed(c)),
+            }
+            if let Slot::OneOf(ref candidates) = slots[0] {
+                if candidates.is_empty() {
+                    return Err(unsatisfiable("no allowed character for the first position"));
+                }
+            }
+        }
+        Ok(slots)
+    }
+
+    // the mandatory class placed in each slot of the template, the slots being tried in the
+    // given order, or `None` if the template cannot produce all of them
+    fn place_mandatory(&self, slots: &[Slot], order: &[usize], requirements: &Requirements) -> Option<Vec<Option<CharClass>>> {
+        let classes = [CharClass::Digit, CharClass::Lowercase, CharClass::Uppercase, CharClass::Other];
+        let literals: Vec<CharClass> = slots.iter()
+            .filter_map(|slot| match *slot {
+                Slot::Literal(c) => Some(CharClass::of(c)),
+                Slot::OneOf(_) => None,
+            })
+            .collect();
+        let available: Vec<CharClass> = classes.iter().cloned().filter(|&class| slots.iter().any(|slot| slot.produces(class))).collect();
+        let mut mandatory = self.mandatory(&available, &literals, requirements);
+        // the literals are always there
+        for class in &literals {
+            if let Some(pos) = mandatory.iter().position(|c| c == class) {
+                mandatory.remove(pos);
+            }
+        }
+        let mut owners = vec![None; slots.len()];
+        for i in 0..mandatory.len() {
+            if !place(i, &mandatory, slots, order, &mut owners, &mut vec![false; slots.len()]) {
+                return None;
+            }
+        }
+        Some(owners.into_iter().map(|owner| owner.map(|i| mandatory[i])).collect())
+    }
+
+    /// Checks that the specification is consistent, and can produce passwords meeting the
+    /// given requirements.
+    pub(crate) fn validate(&self, requirements: &Requirements) -> Result<(), Error> {
+        let allowed = self.allowed();
+        if allowed.is_empty() {
+            return Err(unsatisfiable("no allowed character"));
+        }
+        let available = |class: CharClass| allowed.iter().any(|&c| CharClass::of(c) == class);
+        let required = [
+            (CharClass::Digit, requirements.digits, "digits"),
+            (CharClass::Lowercase, requirements.lowercase, "lowercase letters"),
+            (CharClass::Uppercase, requirements.uppercase, "uppercase letters"),
+            (CharClass::Other, requirements.others, "other characters"),
+        ];
+        for &(class, count, name) in &required {
+            if count > 0 && !available(class) {
+                return Err(unsatisfiable(format!("the policy requires {} {} but none is allowed", count, name)));
+            }
+        }
+        let classes = [CharClass::Digit, CharClass::Lowercase, CharClass::Uppercase, CharClass::Other];
+        let available_classes = classes.iter().filter(|&&c| available(c)).count();
+        if available_classes < requirements.min_classes {
+            return Err(unsatisfiable(format!("the policy requires {} classes but only {} are allowed", requirements.min_classes, available_classes)));
+        }
+        for &class in &self.required_classes {
+            if !available(class) {
+                return Err(unsatisfiable(format!("class {:?} is required but none of its characters is allowed", class)));
+            }
+        }
+        let max_length = match self.template {
+            Some(ref template) => self.parse_template(template, &allowed)?.len(),
+            None => self.max_length,
+        };
+        // each character counts for one, plus the credits for the classes
+        if max_length + requirements.max_credit < requirements.min_length {
+            return Err(unsatisfiable(format!("the policy requires at least {} characters but the maximum length is {}", requirements.min_length, max_length)));
+        }
+        if let Some(ref template) = self.template {
+            let slots = self.parse_template(template, &allowed)?;
+            let room = |class: CharClass| slots.iter().filter(|slot| slot.produces(class)).count();
+            for &(class, count, name) in &required {
+                if count > room(class) {
+                    return Err(unsatisfiable(format!("the policy requires {} {} but the template has room for {}", count, name, room(class))));
+                }
+            }
+            let template_classes = classes.iter().filter(|&&c| room(c) > 0).count();
+            if template_classes < requirements.min_classes {
+                return Err(unsatisfiable(format!("the policy requires {} classes but the template produces only {}", requirements.min_classes, template_classes)));
+            }
+            for &class in &self.required_classes {
+                if room(class) == 0 {
+                    return Err(unsatisfiable(format!("class {:?} is required but the template cannot produce it", class)));
+                }
+            }
+            let order: Vec<usize> = (0..slots.len()).collect();
+            if self.place_mandatory(&slots, &order, requirements).is_none() {
+                return Err(unsatisfiable("the template has no room for all the required classes"));
+            }
+        } else {
+            if self.min_length == 0 || self.min_length > self.max_length {
+                return Err(unsatisfiable(format!("invalid length range {}..{}", self.min_length, self.max_length)));
+            }
+            // the mandatory characters cannot take the constrained first position
+            let needed = self.mandatory(&Self::classes_of(&allowed), &[], requirements).len() + self.first.is_some() as usize;
+            if needed > self.max_length {
+                return Err(unsatisfiable(format!("the policy requires at least {} characters but the maximum length is {}", needed, self.max_length)));
+            }
+            if let Some(ref first) = self.first {
+                if !first.iter().any(|&class| available(class)) {
+                    return Err(unsatisfiable("no allowed character for the first position"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // the classes of the given characters
+    fn classes_of(chars: &[char]) -> Vec<CharClass> {
+        let mut classes: Vec<CharClass> = Vec::new();
+        for &c in chars {
+            if !classes.contains(&CharClass::of(c)) {
+                classes.push(CharClass::of(c));
+            }
+        }
+        classes
+    }
+
+    // the classes that must appear in a generated password, once per required character,
+    // given the classes that can be produced and the ones always present
+    fn mandatory(&self, available: &[CharClass], present: &[CharClass], requirements: &Requirements) -> Vec<CharClass> {
+        let mut mandatory: Vec<CharClass> = Vec::new();
+        let required = [
+            (CharClass::Digit, requirements.digits),
+            (CharClass::Lowercase, requirements.lowercase),
+            (CharClass::Uppercase, requirements.uppercase),
+            (CharClass::Other, requirements.others),
+        ];
+        for &(class, count) in &required {
+            mandatory.extend((0..count).map(|_| class));
+        }
+        for &class in &self.required_classes {
+            if !mandatory.contains(&class) {
+                mandatory.push(class);
+            }
+        }
+        let classes = [CharClass::Lowercase, CharClass::Uppercase, CharClass::Digit, CharClass::Other];
+        for &class in &classes {
+            if classes.iter().filter(|c| mandatory.contains(c) || present.contains(c)).count() >= requirements.min_classes {
+                break;
+            }
+            if !mandatory.contains(&class) && !present.contains(&class) && available.contains(&class) {
+                mandatory.push(class);
+            }
+        }
+        mandatory
+    }
+
+    /// Generates a password following the specification, ensuring that the required classes
+    /// and the given requirements on classes are met.
+    /// Fails if there is no room for all the required characters, which `validate` reports.
+    pub(crate) fn generate<R: Rng>(&self, rng: &mut R, requirements: &Requirements) -> Result<String, Error> {
+        let allowed = self.allowed();
+        if let Some(ref template) = self.template {
+            let slots = self.parse_template(template, &allowed)?;
+            // the mandatory classes are placed in random slots able to produce them
+            let mut order: Vec<usize> = (0..slots.len()).collect();
+            order.shuffle(rng);
+            let placed = self.place_mandatory(&slots, &order, requirements)
+                .ok_or_else(|| unsatisfiable("the template has no room for all the required classes"))?;
+            return Ok(slots.iter().zip(placed)
+                .map(|(slot, class)| match *slot {
+                    Slot::Literal(c) => c,
+                    Slot::OneOf(ref candidates) => {
+                        let candidates: Vec<char> = candidates.iter().cloned()
+                            .filter(|&c| class.is_none_or(|class| CharClass::of(c) == class))
+                            .collect();
+                        candidates[rng.gen_range(0..candidates.len())]
+                    },
+                })
+                .collect());
+        }
+        let of_class = |class: CharClass| -> Vec<char> { allowed.iter().cloned().filter(|&c| CharClass::of(c) == class).collect() };
+        let mandatory = self.mandatory(&Self::classes_of(&allowed), &[], requirements);
+        // the password is long enough for all the mandatory characters
+        let first_constrained = self.first.is_some();
+        let min_length = self.min_length.max(mandatory.len() + first_constrained as usize);
+        if min_length > self.max_length {
+            return Err(unsatisfiable(format!("the policy requires at least {} characters but the maximum length is {}", min_length, self.max_length)));
+        }
+        let length = rng.gen_range(min_length..=self.max_length);
+        let mut password: Vec<char> = (0..length).map(|_| allowed[rng.gen_range(0..allowed.len())]).collect();
+        // place the mandatory classes at distinct random positions, the first one excepted
+        // when it is constrained
+        let mut positions: Vec<usize> = (if first_constrained { 1 } else { 0 }..length).collect();
+        for class in mandatory {
+            let candidates = of_class(class);
+            let pos = positions.swap_remove(rng.gen_range(0..positions.len()));
+            password[pos] = candidates[rng.gen_range(0..candidates.len())];
+        }
+        if let Some(ref first) = self.first {
+            let candidates: Vec<char> = allowed.iter().cloned().filter(|&c| first.contains(&CharClass::of(c))).collect();
+            if !candidates.is_empty() {
+                password[0] = candidates[rng.gen_range(0..candidates.len())];
+            }
+        }
+        Ok(password.into_iter().collect())
+    }
+
+}
+
+/// The minimum number of bits of entropy of passwords generated by `PWQuality::generate_password_with_rng`.
+pub const MIN_ENTROPY_BITS: i32 = 56;
+
+/// The maximum number of bits of entropy of passwords generated by `PWQuality::generate_password_with_rng`.
+pub const MAX_ENTROPY_BITS: i32 = 256;
+
+/// Generates a password of characters picked uniformly in the default alphabet, with at
+/// least the given number of bits of entropy, clamped like the library does.
+pub(crate) fn random_password<R: Rng>(rng: &mut R, entropy: i32) -> String {
+    let alphabet: Vec<char> = DEFAULT_ALPHABET.chars().collect();
+    let bits = entropy.clamp(MIN_ENTROPY_BITS, MAX_ENTROPY_BITS) as f64;
+    let length = (bits / (alphabet.len() as f64).log2()).ceil() as usize;
+    (0..length).map(|_| alphabet[rng.gen_range(0..alphabet.len())]).collect()
+}
+
+/// Inserts characters at random positions in the password so that it meets the requirements
+/// on classes and length.
+pub(crate) fn repair<R: Rng>(rng: &mut R, password: &str, requirements: &Requirements) -> String {
+    let mut password: Vec<char> = password.chars().collect();
+    let of_class = |class: CharClass| -> Vec<char> { DEFAULT_ALPHABET.chars().filter(|&c| CharClass::of(c) == class).collect() };
+    let count = |password: &[char], class: CharClass| password.iter().filter(|&&c| CharClass::of(c) == class).count();
+    let mut insert = |password: &mut Vec<char>, class: CharClass| {
+        let candidates = of_class(class);
+        let pos = rng.gen_range(0..=password.len());
+        password.insert(pos, candidates[rng.gen_range(0..candidates.len())]);
+    };
+    let required = [
+        (CharClass::Digit, requirements.digits),
+        (CharClass::Lowercase, requirements.lowercase),
+        (CharClass::Uppercase, requirements.uppercase),
+        (CharClass::Other, requirements.others),
+    ];
+    for &(class, min) in &required {
+        while count(&password, class) < min {
+            insert(&mut password, class);
+        }
+    }
+    let classes = [CharClass::Lowercase, CharClass::Uppercase, CharClass::Digit, CharClass::Other];
+    for &class in &classes {
+        if classes.iter().filter(|&&c| count(&password, c) > 0).count() >= requirements.min_classes {
+            break;
+        }
+        if count(&password, class) == 0 {
+            insert(&mut password, class);
+        }
+    }
+    // credits are not accounted for, the password is at least as long as the minimum length
+    while password.len() < requirements.min_length {
+        insert(&mut password, CharClass::Lowercase);
+    }
+    password.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use PWQuality;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn count(password: &str, class: CharClass) -> usize {
+        password.chars().filter(|&c| CharClass::of(c) == class).count()
+    }
+
+    #[test]
+    fn templates() {
+        let spec = GeneratorSpec::from_template("Cvccvc-99\\9");
+        let requirements = Requirements::default();
+        spec.validate(&requirements).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let password: Vec<char> = spec.generate(&mut rng, &requirements).unwrap().chars().collect();
+            assert_eq!(password.len(), 10);
+            assert!(password[0].is_ascii_uppercase() && !VOWELS.contains(password[0]));
+            assert!(password[1].is_ascii_lowercase() && VOWELS.contains(password[1]));
+            assert!(password[2].is_ascii_lowercase() && !VOWELS.contains(password[2]));
+            assert_eq!(password[6], '-');
+            assert!(password[7].is_ascii_digit() && password[8].is_ascii_digit());
+            assert_eq!(password[9], '9');
+        }
+    }
+
+    #[test]
+    fn invalid_templates() {
+        let requirements = Requirements::default();
+        for template in &["", "abc\\", "99"] {
+            let spec = GeneratorSpec {
+                forbidden: "0123456789".to_owned(),
+                ..GeneratorSpec::from_template(*template)
+            };
+            match spec.validate(&requirements) {
+                Err(Error::Unsatisfiable { .. }) => (),
+                result => panic!("unexpected result {:?} for {:?}", result, template),
+            }
+        }
+    }
+
+    #[test]
+    fn templates_with_requirements() {
+        let requirements = Requirements {
+            uppercase: 1,
+            digits: 2,
+            min_classes: 3,
+            ..Requirements::default()
+        };
+        let spec = GeneratorSpec {
+            required_classes: vec![CharClass::Other],
+            first: Some(vec![CharClass::Lowercase]),
+            ..GeneratorSpec::from_template("a*a**-")
+        };
+        spec.validate(&requirements).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let password = spec.generate(&mut rng, &requirements).unwrap();
+            assert!(password.starts_with(|c: char| c.is_ascii_lowercase()), "{}", password);
+            assert!(password.ends_with('-'), "{}", password);
+            assert!(count(&password, CharClass::Uppercase) >= 1, "{}", password);
+            assert!(count(&password, CharClass::Digit) >= 2, "{}", password);
+        }
+        // the literal counts for the required class, leaving room for a lowercase letter
+        let spec = GeneratorSpec {
+            required_classes: vec![CharClass::Other, CharClass::Lowercase],
+            ..GeneratorSpec::from_template("l*-")
+        };
+        let requirements = Requirements {
+            uppercase: 1,
+            ..Requirements::default()
+        };
+        spec.validate(&requirements).unwrap();
+        for _ in 0..20 {
+            let password = spec.generate(&mut rng, &requirements).unwrap();
+            assert!(password.chars().nth(1).unwrap().is_ascii_uppercase(), "{}", password);
+        }
+    }
+
+    #[test]
+    fn unsatisfiable_templates() {
+        let cases = [
+            ("lll9", Requirements { digits: 2, ..Requirements::default() }, None),
+            ("lll9", Requirements { min_classes: 3, ..Requirements::default() }, None),
+            ("a9", Requirements { uppercase: 1, lowercase: 1, ..Requirements::default() }, None),
+            ("**", Requirements { digits: 1, others: 1, uppercase: 1, ..Requirements::default() }, None),
+            ("9aa", Requirements::default(), Some(vec![CharClass::Uppercase])),
+            ("-aa", Requirements::default(), Some(vec![CharClass::Lowercase])),
+        ];
+        for &(template, requirements, ref first) in &cases {
+            let spec = GeneratorSpec {
+                first: first.clone(),
+                ..GeneratorSpec::from_template(template)
+            };
+            match spec.validate(&requirements) {
+                Err(Error::Unsatisfiable { .. }) => (),
+                result => panic!("unexpected result {:?} for {:?}", result, template),
+            }
+        }
+        // no room for the mandatory classes
+        let spec = GeneratorSpec::from_template("a9");
+        let requirements = Requirements { uppercase: 1, lowercase: 1, ..Requirements::default() };
+        assert!(spec.generate(&mut StdRng::seed_from_u64(0), &requirements).is_err());
+        let spec = GeneratorSpec {
+            required_classes: vec![CharClass::Digit],
+            ..GeneratorSpec::from_template("lll")
+        };
+        assert!(spec.validate(&Requirements::default()).is_err());
+    }
+
+    #[test]
+    fn alphabet() {
+        let spec = GeneratorSpec {
+            alphabet: "abc123".to_owned(),
+            forbidden: "c".to_owned(),
+            min_length: 8,
+            max_length: 8,
+            first: Some(vec![CharClass::Digit]),
+            ..GeneratorSpec::default()
+        };
+        let requirements = Requirements::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..50 {
+            let password = spec.generate(&mut rng, &requirements).unwrap();
+            assert_eq!(password.len(), 8);
+            assert!(password.chars().all(|c| "ab123".contains(c)), "{}", password);
+            assert!(password.starts_with(|c: char| c.is_ascii_digit()), "{}", password);
+        }
+    }
+
+    #[test]
+    fn repair() {
+        let requirements = Requirements {
+            min_length: 12,
+            digits: 2,
+            uppercase: 1,
+            others: 1,
+            min_classes: 4,
+            ..Requirements::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for password in &["", "abc", "ABCDEFGHIJKLMNOP", "0123"] {
+            let repaired = super::repair(&mut rng, password, &requirements);
+            assert!(repaired.len() >= 12, "{}", repaired);
+            assert!(count(&repaired, CharClass::Digit) >= 2, "{}", repaired);
+            assert!(count(&repaired, CharClass::Uppercase) >= 1, "{}", repaired);
+            assert!(count(&repaired, CharClass::Lowercase) >= 1, "{}", repaired);
+            assert!(count(&repaired, CharClass::Other) >= 1, "{}", repaired);
+        }
+        // a compliant password is left unchanged
+        assert_eq!(super::repair(&mut rng, "aB3$aB3$aB3$", &requirements), "aB3$aB3$aB3$");
+    }
+
+    #[test]
+    fn random_passwords() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // 94 characters, about 6.55 bits each
+        assert_eq!(random_password(&mut rng, 0).len(), 9);
+        assert_eq!(random_password(&mut rng, 64).len(), 10);
+        assert_eq!(random_password(&mut rng, 1000).len(), 40);
+    }
+
+    #[cfg(feature = "test-util")]
+    #[test]
+    fn deterministic() {
+        use testing::seeded_rng;
+
+        let pwq = PWQuality::new();
+        let spec = GeneratorSpec::default();
+        assert_eq!(pwq.generate_password_with_rng(&mut seeded_rng(42), 64), pwq.generate_password_with_rng(&mut seeded_rng(42), 64));
+        assert_eq!(pwq.generate_compliant_with_rng(&mut seeded_rng(42), 64), pwq.generate_compliant_with_rng(&mut seeded_rng(42), 64));
+        assert_eq!(pwq.generate_from_spec_with_rng(&mut seeded_rng(42), &spec), pwq.generate_from_spec_with_rng(&mut seeded_rng(42), &spec));
+        assert_ne!(pwq.generate_password_with_rng(&mut seeded_rng(42), 64), pwq.generate_password_with_rng(&mut seeded_rng(43), 64));
+    }
+
+    #[test]
+    fn compliant_passwords() {
+        // (minimum length, digit credit, uppercase credit, lowercase credit, other credit, minimum classes)
+        let policies = [
+            (6, 1, 1, 1, 1, 0),
+            (16, 0, 0, 0, 0, 4),
+            (12, -3, -2, -2, -2, 4),
+            (20, -1, -1, -1, -1, 3),
+            (10, -4, 0, 0, 0, 2),
+            (14, 0, -3, 0, -3, 3),
+        ];
+        for &(min_length, digit, upper, lower, other, classes) in &policies {
+            let pwq = PWQuality::new();
+            pwq.set_min_length(min_length);
+            pwq.set_digit_credit(digit);
+            pwq.set_uppercase_credit(upper);
+            pwq.set_lowercase_credit(lower);
+            pwq.set_other_credit(other);
+            pwq.set_min_classes(classes);
+            for _ in 0..10 {
+                let password = pwq.generate_compliant(64).unwrap();
+                assert!(pwq.check(password.clone(), None, None).is_ok(), "{}", password);
+                let requirements = Requirements::from_settings(&pwq);
+                assert!(count(&password, CharClass::Digit) >= requirements.digits, "{}", password);
+                assert!(count(&password, CharClass::Uppercase) >= requirements.uppercase, "{}", password);
+                assert!(count(&password, CharClass::Lowercase) >= requirements.lowercase, "{}", password);
+                assert!(count(&password, CharClass::Other) >= requirements.others, "{}", password);
+            }
+        }
+    }
+
+    #[test]
+    fn mandatory_classes_in_short_passwords() {
+        let spec = GeneratorSpec {
+            required_classes: vec![CharClass::Digit, CharClass::Uppercase, CharClass::Lowercase, CharClass::Other],
+            min_length: 1,
+            max_length: 4,
+            ..GeneratorSpec::default()
+        };
+        let requirements = Requirements::default();
+        spec.validate(&requirements).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let password = spec.generate(&mut rng, &requirements).unwrap();
+            assert_eq!(password.chars().count(), 4);
+            for &class in &spec.required_classes {
+                assert_eq!(count(&password, class), 1, "{}", password);
+            }
+        }
+    }
+
+    #[test]
+    fn no_room_for_mandatory_classes() {
+        let spec = GeneratorSpec {
+            required_classes: vec![CharClass::Digit, CharClass::Uppercase, CharClass::Lowercase, CharClass::Other],
+            min_length: 1,
+            max_length: 4,
+            first: Some(vec![CharClass::Lowercase]),
+            ..GeneratorSpec::default()
+        };
+        let requirements = Requirements::default();
+        match spec.validate(&requirements) {
+            Err(Error::Unsatisfiable { .. }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        match spec.generate(&mut StdRng::seed_from_u64(0), &requirements) {
+            Err(Error::Unsatisfiable { .. }) => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+
+}
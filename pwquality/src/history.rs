@@ -0,0 +1,280 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Detection of the reuse of previous passwords stored as hashes.
+//!
+//! Previous passwords are given as hashes in the crypt(3) format, as found in `/etc/shadow`
+//! or `/etc/security/opasswd`. SHA-512-crypt (`$6$`), yescrypt (`$y$`) and bcrypt (`$2b$`)
+//! hashes are verified with the crypt library of the system, Argon2 hashes in the PHC string
+//! format (`$argon2id$`) are verified by the crate.
+//!
+//! As hashes cannot be compared, near-reuse is detected by hashing variants of the new
+//! password: the password with changed case, and with its trailing number decremented or
+//! removed. Every variant is hashed with every previous hash, which is slow by design of
+//! these hash functions, so the number of variants is kept small.
+
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+
+use libc::{c_char, c_void};
+
+use pwquality_sys::Error;
+
+use std::ffi::{CStr, CString};
+
+#[link(name = "crypt")]
+extern "C" {
+    fn crypt_r(phrase: *const c_char, setting: *const c_char, data: *mut c_void) -> *mut c_char;
+}
+
+// the size of `struct crypt_data` in glibc (131232 bytes), larger than the one of libxcrypt
+// (32768 bytes of internal data plus the buffers, about 34 KiB)
+const CRYPT_DATA_SIZE: usize = 131_232;
+
+/// The hash functions of the crypt(3) format supported in histories.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HashScheme {
+    /// SHA-512-crypt, prefixed with `$6$`.
+    Sha512Crypt,
+    /// yescrypt, prefixed with `$y$`.
+    Yescrypt,
+    /// bcrypt, prefixed with `$2a$`, `$2b$` or `$2y$`.
+    Bcrypt,
+    /// Argon2 in the PHC string format, prefixed with `$argon2i$`, `$argon2d$` or `$argon2id$`.
+    Argon2,
+}
+
+impl HashScheme {
+
+    /// Returns the scheme of the given hash, if it is supported.
+    pub fn of(hash: &str) -> Option<HashScheme> {
+        if hash.starts_with("$6$") {
+            Some(HashScheme::Sha512Crypt)
+        } else if hash.starts_with("$y$") {
+            Some(HashScheme::Yescrypt)
+        } else if hash.starts_with("$2a$") || hash.starts_with("$2b$") || hash.starts_with("$2y$") {
+            Some(HashScheme::Bcrypt)
+        } else if hash.starts_with("$argon2i$") || hash.starts_with("$argon2d$") || hash.starts_with("$argon2id$") {
+            Some(HashScheme::Argon2)
+        } else {
+            None
+        }
+    }
+
+}
+
+/// How a password reuses a previous one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reuse {
+    /// The password is the same as the previous one.
+    Exact,
+    /// The password only differs from the previous one by the case of its letters.
+    CaseChange,
+    /// The password is the previous one with its trailing number incremented, or a digit appended.
+    Increment,
+}
+
+impl Reuse {
+
+    /// Returns the error of the library describing this reuse.
+    pub fn error(self) -> Error {
+        match self {
+            Reuse::Exact => Error::SamePassword,
+            Reuse::CaseChange => Error::CaseChangesOnly,
+            Reuse::Increment => Error::TooSimilar,
+        }
+    }
+
+}
+
+/// The hashes of the previous passwords of a user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PasswordHistory {
+    hashes: Vec<String>,
+    max_increment: u32,
+}
+
+impl PasswordHistory {
+
+    /// Creates a history from the given hashes, most recent first.
+    pub fn new(hashes: Vec<String>) -> Self {
+        PasswordHistory {
+            hashes,
+            max_increment: 3,
+        }
+    }
+
+    /// Sets by how much the trailing number of a previous password may have been
+    /// incremented to be considered reused (3 by default).
+    /// A value of 0 disables the detection of increments.
+    pub fn set_max_increment(&mut self, max: u32) {
+        self.max_increment = max;
+    }
+
+    /// Returns by how much the trailing number of a previous password may have been
+    /// incremented to be considered reused.
+    pub fn get_max_increment(&self) -> u32 {
+        self.max_increment
+    }
+
+    /// Returns the hashes of the history.
+    pub fn hashes(&self) -> &[String] {
+        &self.hashes
+    }
+
+    /// Returns the index in the history of the first previous password reused by the
+    /// given password, and how it is reused.
+    /// Hashes of unsupported schemes never match.
+    pub fn find_reuse(&self, password: &str) -> Option<(usize, Reuse)> {
+        let candidates = self.variants(password);
+        self.hashes.iter().enumerate().filter_map(|(index, hash)| {
+            candidates.iter()
+                .find(|&(candidate, _)| verify(candidate, hash))
+                .map(|&(_, reuse)| (index, reuse))
+        }).next()
+    }
+
+    /// Checks that the password does not reuse a previous password, and returns a
+    /// `History` error giving the index of the reused password otherwise.
+    pub fn check(&self, password: &str) -> Result<(), Error> {
+        match self.find_reuse(password) {
+            Some((index, reuse)) => Err(Error::History { index, reason: Box::new(reuse.error()) }),
+            None => Ok(()),
+        }
+    }
+
+    // the passwords that would be a reuse of a previous one with the same hash
+    fn variants(&self, password: &str) -> Vec<(String, Reuse)> {
+        let mut variants: Vec<(String, Reuse)> = vec![(password.to_owned(), Reuse::Exact)];
+        let mut add = |candidate: String, reuse: Reuse| {
+            if !candidate.is_empty() && !variants.iter().any(|(c, _)| *c == candidate) {
+                variants.push((candidate, reuse));
+            }
+        };
+        add(password.to_lowercase(), Reuse::CaseChange);
+        add(password.to_uppercase(), Reuse::CaseChange);
+        add(swap_case(password), Reuse::CaseChange);
+        let mut chars = password.chars();
+        if let Some(first) = chars.next() {
+            add(first.to_uppercase().chain(chars.clone().flat_map(char::to_lowercase)).collect(), Reuse::CaseChange);
+            add(first.to_lowercase().chain(chars).collect(), Reuse::CaseChange);
+        }
+        if self.max_increment > 0 {
+            let prefix = password.trim_end_matches(|c: char| c.is_ascii_digit());
+            let digits = &password[prefix.len()..];
+            if !digits.is_empty() {
+                // a digit was appended to the previous password
+                add(password[..password.len() - 1].to_owned(), Reuse::Increment);
+                if let Ok(number) = digits.parse::<u64>() {
+                    for decrement in 1..=u64::from(self.max_increment).min(number) {
+                        add(format!("{}{:0width$}", prefix, number - decrement, width = digits.len()), Reuse::Increment);
+                        add(format!("{}{}", prefix, number - decrement), Reuse::Increment);
+                    }
+                }
+            }
+        }
+        variants
+    }
+
+}
+
+impl Default for PasswordHistory {
+    fn default() -> Self {
+        PasswordHistory::new(Vec::new())
+    }
+}
+
+fn swap_case(password: &str) -> String {
+    password.chars().flat_map(|c| {
+        let swapped: Vec<char> = if c.is_uppercase() { c.to_lowercase().collect() } else { c.to_uppercase().collect() };
+        swapped
+    }).collect()
+}
+
+/// Returns whether the password matches the given crypt(3) hash.
+/// Hashes of unsupported schemes never match.
+pub fn verify(password: &str, hash: &str) -> bool {
+    match HashScheme::of(hash) {
+        Some(HashScheme::Argon2) => match PasswordHash::new(hash) {
+            Ok(parsed) => Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok(),
+            Err(_) => false,
+        },
+        Some(_) => crypt(password, hash).map(|hashed| hashed == hash).unwrap_or(false),
+        None => false,
+    }
+}
+
+// hashes the password with the setting of the given hash using the crypt library of the system
+fn crypt(password: &str, setting: &str) -> Option<String> {
+    let phrase = CString::new(password).ok()?;
+    let setting = CString::new(setting).ok()?;
+    // zeroed as required by `crypt_r`, and aligned for the integers of the structure
+    let mut data = vec![0u64; CRYPT_DATA_SIZE.div_ceil(8)];
+    let hashed = unsafe {
+        let ptr = crypt_r(phrase.as_ptr(), setting.as_ptr(), data.as_mut_ptr() as *mut c_void);
+        if ptr.is_null() {
+            return None;
+        }
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    };
+    // failures are reported with a string starting with `*` by some implementations
+    if hashed.starts_with('*') {
+        None
+    } else {
+        Some(hashed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use argon2::{Algorithm, Params, Version};
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    const SHA512: &str = "$6$saltsalt$WsOP.DIlYIRPN4s.l6y16/PUSGO8NRHPHKTGC9mHoNcAPrq0MaIeKw.thLMyFCFdwqqWb/bwG9M/kaKzun8Bq/";
+
+    fn argon2(password: &str) -> String {
+        let salt = SaltString::from_b64("c29tZXNhbHRzYWx0").unwrap();
+        // cheap parameters, the verification uses the ones of the hash
+        let params = Params::new(64, 1, 1, None).unwrap();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params).hash_password(password.as_bytes(), &salt).unwrap().to_string()
+    }
+
+    #[test]
+    fn schemes() {
+        assert_eq!(HashScheme::of(SHA512), Some(HashScheme::Sha512Crypt));
+        assert_eq!(HashScheme::of("$y$j9T$salt$hash"), Some(HashScheme::Yescrypt));
+        assert_eq!(HashScheme::of("$2b$10$hash"), Some(HashScheme::Bcrypt));
+        assert_eq!(HashScheme::of(&argon2("Secret42")), Some(HashScheme::Argon2));
+        assert_eq!(HashScheme::of("$1$salt$hash"), None);
+    }
+
+    #[test]
+    fn verification() {
+        assert!(verify("Secret42", SHA512));
+        assert!(!verify("Secret43", SHA512));
+        assert!(verify("Secret42", &argon2("Secret42")));
+        assert!(!verify("Secret43", &argon2("Secret42")));
+        assert!(!verify("Secret42", "$1$saltsalt$whatever"));
+    }
+
+    #[test]
+    fn reuse() {
+        let history = PasswordHistory::new(vec![argon2("Other7"), SHA512.to_owned()]);
+        assert_eq!(history.find_reuse("Secret42"), Some((1, Reuse::Exact)));
+        assert_eq!(history.find_reuse("sECRET42"), Some((1, Reuse::CaseChange)));
+        assert_eq!(history.find_reuse("Secret45"), Some((1, Reuse::Increment)));
+        assert_eq!(history.find_reuse("Secret420"), Some((1, Reuse::Increment)));
+        assert_eq!(history.find_reuse("Secret46"), None);
+        assert_eq!(history.find_reuse("other7"), Some((0, Reuse::CaseChange)));
+        match history.check("Secret43") {
+            Err(Error::History { index: 1, reason }) => assert_eq!(*reason, Error::TooSimilar),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let mut history = history;
+        history.set_max_increment(0);
+        assert_eq!(history.find_reuse("Secret43"), None);
+    }
+
+}
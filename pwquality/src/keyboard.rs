@@ -0,0 +1,287 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Keyboard layouts and detection of keyboard walks such as `qwerty` or `zaq1xsw2`.
+//!
+//! A layout is described by its rows of keys, each key listing the characters it produces
+//! (unshifted first). The adjacency graph of the keys is derived from their position:
+//! on slanted keyboards each row is shifted by half a key from the previous one, so a key
+//! has up to 6 neighbours, on aligned keyboards (keypads) a key has up to 8 neighbours.
+
+use std::collections::HashMap;
+
+/// A keyboard layout and the adjacency graph of its keys.
+#[derive(Clone, Debug)]
+pub struct KeyboardLayout {
+    name: &'static str,
+    // the key producing each character, and whether the character is shifted
+    chars: HashMap<char, (usize, bool)>,
+    // for each key, its neighbour key in each direction
+    neighbours: Vec<Vec<Option<usize>>>,
+    average_degree: f64,
+}
+
+/// A walk on adjacent keys of a keyboard found in a password.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Walk {
+    /// The name of the layout on which the walk was found.
+    pub layout: &'static str,
+    /// The index (in characters) of the first character of the walk in the password.
+    pub start: usize,
+    /// The index (in characters) after the last character of the walk in the password.
+    pub end: usize,
+    /// The number of changes of direction in the walk.
+    pub turns: usize,
+    /// The number of shifted characters in the walk.
+    pub shifted: usize,
+}
+
+impl Walk {
+
+    /// Returns the number of characters in the walk.
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Returns whether the walk is empty.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+}
+
+// row offset and keys of each row
+type Rows = &'static [(usize, &'static [&'static str])];
+
+const QWERTY: Rows = &[
+    (0, &["`~", "1!", "2@", "3#", "4$", "5%", "6^", "7&", "8*", "9(", "0)", "-_", "=+"]),
+    (1, &["qQ", "wW", "eE", "rR", "tT", "yY", "uU", "iI", "oO", "pP", "[{", "]}", "\\|"]),
+    (1, &["aA", "sS", "dD", "fF", "gG", "hH", "jJ", "kK", "lL", ";:", "'\""]),
+    (1, &["zZ", "xX", "cC", "vV", "bB", "nN", "mM", ",<", ".>", "/?"]),
+];
+
+const AZERTY: Rows = &[
+    (0, &["²", "&1", "é2", "\"3", "'4", "(5", "-6", "è7", "_8", "ç9", "à0", ")°", "=+"]),
+    (1, &["aA", "zZ", "eE", "rR", "tT", "yY", "uU", "iI", "oO", "pP", "^¨", "$£"]),
+    (1, &["qQ", "sS", "dD", "fF", "gG", "hH", "jJ", "kK", "lL", "mM", "ù%", "*µ"]),
+    (0, &["<>", "wW", "xX", "cC", "vV", "bB", "nN", ",?", ";.", ":/", "!§"]),
+];
+
+const QWERTZ: Rows = &[
+    (0, &["^°", "1!", "2\"", "3§", "4$", "5%", "6&", "7/", "8(", "9)", "0=", "ß?", "´`"]),
+    (1, &["qQ", "wW", "eE", "rR", "tT", "zZ", "uU", "iI", "oO", "pP", "üÜ", "+*"]),
+    (1, &["aA", "sS", "dD", "fF", "gG", "hH", "jJ", "kK", "lL", "öÖ", "äÄ", "#'"]),
+    (0, &["<>", "yY", "xX", "cC", "vV", "bB", "nN", "mM", ",;", ".:", "-_"]),
+];
+
+const DVORAK: Rows = &[
+    (0, &["`~", "1!", "2@", "3#", "4$", "5%", "6^", "7&", "8*", "9(", "0)", "[{", "]}"]),
+    (1, &["'\"", ",<", ".>", "pP", "yY", "fF", "gG", "cC", "rR", "lL", "/?", "=+", "\\|"]),
+    (1, &["aA", "oO", "eE", "uU", "iI", "dD", "hH", "tT", "nN", "sS", "-_"]),
+    (1, &[";:", "qQ", "jJ", "kK", "xX", "bB", "mM", "wW", "vV", "zZ"]),
+];
+
+const KEYPAD: Rows = &[
+    (1, &["/", "*", "-"]),
+    (0, &["7", "8", "9", "+"]),
+    (0, &["4", "5", "6"]),
+    (0, &["1", "2", "3"]),
+    (1, &["0", "."]),
+];
+
+impl KeyboardLayout {
+
+    /// The US QWERTY layout.
+    pub fn qwerty() -> Self {
+        KeyboardLayout::build("qwerty", QWERTY, true)
+    }
+
+    /// The French AZERTY layout.
+    pub fn azerty() -> Self {
+        KeyboardLayout::build("azerty", AZERTY, true)
+    }
+
+    /// The German QWERTZ layout.
+    pub fn qwertz() -> Self {
+        KeyboardLayout::build("qwertz", QWERTZ, true)
+    }
+
+    /// The US Dvorak layout.
+    pub fn dvorak() -> Self {
+        KeyboardLayout::build("dvorak", DVORAK, true)
+    }
+
+    /// The numeric keypad.
+    pub fn keypad() -> Self {
+        KeyboardLayout::build("keypad", KEYPAD, false)
+    }
+
+    /// Returns the built-in layout with the given name (`qwerty`, `azerty`, `qwertz`, `dvorak` or `keypad`).
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "qwerty" => Some(KeyboardLayout::qwerty()),
+            "azerty" => Some(KeyboardLayout::azerty()),
+            "qwertz" => Some(KeyboardLayout::qwertz()),
+            "dvorak" => Some(KeyboardLayout::dvorak()),
+            "keypad" => Some(KeyboardLayout::keypad()),
+            _ => None,
+        }
+    }
+
+    /// Returns all the built-in layouts.
+    pub fn all() -> Vec<KeyboardLayout> {
+        vec![
+            KeyboardLayout::qwerty(),
+            KeyboardLayout::azerty(),
+            KeyboardLayout::qwertz(),
+            KeyboardLayout::dvorak(),
+            KeyboardLayout::keypad(),
+        ]
+    }
+
+    fn build(name: &'static str, rows: Rows, slanted: bool) -> Self {
+        let mut positions = HashMap::new();
+        let mut keys = Vec::new();
+        for (y, &(offset, row)) in rows.iter().enumerate() {
+            for (x, key) in row.iter().enumerate() {
+                let pos = ((offset + x) as isize, y as isize);
+                positions.insert(pos, keys.len());
+                keys.push((*key, pos));
+            }
+        }
+        let directions: &[(isize, isize)] = if slanted {
+            &[(-1, 0), (0, -1), (1, -1), (1, 0), (0, 1), (-1, 1)]
+        } else {
+            &[(-1, 0), (-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1)]
+        };
+        let mut chars = HashMap::new();
+        let mut neighbours = Vec::with_capacity(keys.len());
+        let mut degrees = 0;
+        for (idx, &(key, (x, y))) in keys.iter().enumerate() {
+            let adjacent: Vec<Option<usize>> = directions.iter()
+                .map(|&(dx, dy)| positions.get(&(x + dx, y + dy)).cloned())
+                .collect();
+            degrees += adjacent.iter().filter(|n| n.is_some()).count();
+            neighbours.push(adjacent);
+            for (i, c) in key.chars().enumerate() {
+                chars.entry(c).or_insert((idx, i > 0));
+            }
+        }
+        KeyboardLayout {
+            name,
+            chars,
+            average_degree: degrees as f64 / keys.len() as f64,
+            neighbours,
+        }
+    }
+
+    /// Returns the name of this layout.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Returns the number of keys on this layout.
+    pub fn keys(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    /// Returns the average number of neighbours of a key on this layout.
+    pub fn average_degree(&self) -> f64 {
+        self.average_degree
+    }
+
+    // returns the direction in which the key of `next` is adjacent to the key of `current`, if any
+    fn direction(&self, current: char, next: char) -> Option<usize> {
+        let &(current, _) = self.chars.get(&current)?;
+        let &(next, _) = self.chars.get(&next)?;
+        self.neighbours[current].iter().position(|&n| n == Some(next))
+    }
+
+    fn is_shifted(&self, c: char) -> bool {
+        self.chars.get(&c).map(|&(_, shifted)| shifted).unwrap_or(false)
+    }
+
+    /// Returns the longest walk on this layout found in the password, if any.
+    pub fn longest_walk(&self, password: &str) -> Option<Walk> {
+        self.walks(password).into_iter().fold(None, |longest: Option<Walk>, walk| match longest {
+            Some(ref longest) if longest.len() >= walk.len() => Some(longest.clone()),
+            _ => Some(walk),
+        })
+    }
+
+    /// Returns the maximal walks of at least 2 characters on this layout found in the password.
+    pub fn walks(&self, password: &str) -> Vec<Walk> {
+        let chars: Vec<char> = password.chars().collect();
+        let mut walks = Vec::new();
+        let mut start = 0;
+        while start + 1 < chars.len() {
+            let mut end = start + 1;
+            let mut turns = 0;
+            let mut last_direction = None;
+            while end < chars.len() {
+                match self.direction(chars[end - 1], chars[end]) {
+                    Some(direction) => {
+                        if last_direction != Some(direction) {
+                            turns += 1;
+                            last_direction = Some(direction);
+                        }
+                        end += 1;
+                    },
+                    None => break,
+                }
+            }
+            if end - start > 1 {
+                let shifted = chars[start..end].iter().filter(|&&c| self.is_shifted(c)).count();
+                walks.push(Walk {
+                    layout: self.name,
+                    start,
+                    end,
+                    turns,
+                    shifted,
+                });
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+        walks
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn longest(layout: &KeyboardLayout, password: &str) -> usize {
+        layout.longest_walk(password).map(|walk| walk.len()).unwrap_or(0)
+    }
+
+    #[test]
+    fn walks() {
+        let qwerty = KeyboardLayout::qwerty();
+        assert_eq!(longest(&qwerty, "qwerty"), 6);
+        assert_eq!(longest(&qwerty, "xxasdfghxx"), 6);
+        assert_eq!(longest(&qwerty, "zaq1xsw2"), 4);
+        assert_eq!(longest(&qwerty, "pq"), 0);
+        assert_eq!(longest(&KeyboardLayout::azerty(), "azerty"), 6);
+        assert_eq!(longest(&KeyboardLayout::qwertz(), "qwertz"), 6);
+        assert_eq!(longest(&KeyboardLayout::dvorak(), "aoeu"), 4);
+        assert_eq!(longest(&KeyboardLayout::keypad(), "7896"), 4);
+    }
+
+    #[test]
+    fn short_walks() {
+        let qwerty = KeyboardLayout::qwerty();
+        let walks = qwerty.walks("xqwx");
+        assert_eq!(walks, vec![Walk { layout: "qwerty", start: 1, end: 3, turns: 1, shifted: 0 }]);
+        assert_eq!(longest(&qwerty, "x0x"), 0);
+    }
+
+    #[test]
+    fn turns_and_shifts() {
+        let walk = KeyboardLayout::qwerty().longest_walk("QWEdc").unwrap();
+        assert_eq!((walk.len(), walk.turns, walk.shifted), (5, 2, 3));
+    }
+
+}
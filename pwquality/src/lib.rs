@@ -1,23 +1,108 @@
 // Copyright (c) 2017, Lucas Satabin
 // Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
 extern crate pwquality_sys;
+extern crate aho_corasick;
+extern crate argon2;
 extern crate libc;
+extern crate md4;
+extern crate memmap2;
+extern crate rand;
+#[cfg(feature = "test-util")]
+extern crate rand_chacha;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+extern crate sha1;
+#[cfg(feature = "unicode")]
+extern crate unicode_normalization;
+#[cfg(feature = "unicode")]
+extern crate unicode_segmentation;
+#[cfg(all(test, feature = "serde"))]
+extern crate jsonschema;
+#[cfg(all(test, feature = "serde"))]
+#[macro_use]
+extern crate serde_json;
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod bad_words;
+pub mod breach;
+pub mod context;
+pub mod enforcement;
+pub mod estimate;
+pub mod gecos;
+pub mod generator;
+pub mod history;
+pub mod keyboard;
+pub mod passphrase;
+pub mod policy;
+pub mod pronounceable;
+pub mod report;
+pub mod rule;
+pub mod suggestion;
+#[cfg(feature = "test-util")]
+pub mod testing;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+
+mod requirements;
+mod users;
 
 use pwquality_sys::*;
 
+use bad_words::{BadPhraseList, BadWordList};
+use breach::BreachDatabase;
+use context::{contains_word, UserContext};
+use enforcement::{Bypass, UserCheck};
+use estimate::Estimator;
+use gecos::GecosProvider;
+use generator::GeneratorSpec;
+use history::PasswordHistory;
+use keyboard::KeyboardLayout;
+use passphrase::{Passphrase, PassphraseOptions};
+use pronounceable::{Pronounceable, PronounceableOptions};
+use requirements::Requirements;
+use report::{CheckReport, FullCheck};
+use rule::{CheckContext, Rule, RuleOutcome, Rules};
+
 use libc::c_char;
 
+use rand::{CryptoRng, RngCore};
+use rand::rngs::OsRng;
+
+use std::cell::{Cell, RefCell};
 use std::ffi::{CStr, CString};
+use std::io;
 use std::ptr::{null, null_mut};
 
 use std::path::Path;
 
+/// The maximum number of passwords generated by `PWQuality::generate_compliant`
+/// before giving up.
+pub const MAX_COMPLIANT_ATTEMPTS: usize = 32;
+
+// the lowest minimum length accepted by the library
+const LIBRARY_MIN_LENGTH: i32 = 6;
+
 /// Representation of an instance of `pwquality`.
 /// Each instance has its own settings, that can be
 /// changed.
 #[derive(Debug)]
 pub struct PWQuality {
-    pwq: *const pwquality_settings_t
+    pwq: *const pwquality_settings_t,
+    breach: RefCell<Option<BreachDatabase>>,
+    breach_threshold: Cell<u64>,
+    rules: RefCell<Rules>,
+    estimator: RefCell<Estimator>,
+    max_keyboard_walk: Cell<i32>,
+    keyboard_layouts: RefCell<Vec<KeyboardLayout>>,
+    gecos_provider: RefCell<gecos::Provider>,
+    bad_word_list: RefCell<Option<BadWordList>>,
+    bad_phrase_list: RefCell<Option<BadPhraseList>>,
+    #[cfg(feature = "unicode")]
+    unicode_options: Cell<Option<unicode::UnicodeOptions>>,
 }
 
 impl PWQuality {
@@ -27,8 +112,23 @@ impl PWQuality {
         let pwq = unsafe {
             pwquality_default_settings()
         };
+        PWQuality::from_settings(pwq)
+    }
+
+    fn from_settings(pwq: *const pwquality_settings_t) -> Self {
         PWQuality {
-            pwq
+            pwq,
+            breach: RefCell::new(None),
+            breach_threshold: Cell::new(1),
+            rules: RefCell::new(Rules::default()),
+            estimator: RefCell::new(Estimator::new()),
+            max_keyboard_walk: Cell::new(0),
+            keyboard_layouts: RefCell::new(KeyboardLayout::all()),
+            gecos_provider: RefCell::new(gecos::Provider::default()),
+            bad_word_list: RefCell::new(None),
+            bad_phrase_list: RefCell::new(None),
+            #[cfg(feature = "unicode")]
+            unicode_options: Cell::new(None),
         }
     }
 
@@ -47,22 +147,32 @@ impl PWQuality {
     /// Creates a new instance with given configuration file.
     /// If `None` is passed, then default configuration is used.
     pub fn from_optional_config<P: AsRef<Path>>(config_path: Option<P>) -> Result<Self, Error> {
-        let c_path = match config_path {
-            Some(config_path) => CString::new(config_path.as_ref().to_str().unwrap()).unwrap().as_ptr(),
-            None => null()
-        };
+        // the path must outlive the call to the library
+        let c_path = config_path.map(|config_path| CString::new(config_path.as_ref().to_str().unwrap()).unwrap());
 
         let (res, pwq) = unsafe {
             let pwq = pwquality_default_settings();
-            let res = pwquality_read_config(pwq, c_path, null_mut());
+            let res = pwquality_read_config(pwq, c_path.as_ref().map_or(null(), |path| path.as_ptr()), null_mut());
             (res, pwq)
         };
         if res < 0 {
             Err(Error::from_int(res))
         } else {
-            Ok(PWQuality {
-                pwq
-            })
+            Ok(PWQuality::from_settings(pwq))
+        }
+    }
+
+    /// Sets a setting from an option in the format of the configuration file
+    /// (e.g. `minlen=12` or `enforce_for_root`).
+    pub fn set_option(&self, option: &str) -> Result<(), Error> {
+        let c_option = CString::new(option).map_err(|_| Error::UnknownSetting)?;
+        let res = unsafe {
+            pwquality_set_option(self.pwq, c_option.as_ptr())
+        };
+        if res < 0 {
+            Err(Error::from_int(res))
+        } else {
+            Ok(())
         }
     }
 
@@ -291,6 +401,30 @@ impl PWQuality {
         }
     }
 
+    /// Sets the maximum size allowed for walks on adjacent keys of a keyboard such as `qwerty`
+    /// or `zaq1xsw2` in a password.
+    /// A value of `0` disables this check.
+    pub fn set_max_keyboard_walk(&self, max: i32) {
+        self.max_keyboard_walk.set(max.max(0));
+    }
+
+    /// Returns the maximum size allowed for walks on adjacent keys of a keyboard such as `qwerty`
+    /// or `zaq1xsw2` in a password.
+    pub fn get_max_keyboard_walk(&self) -> i32 {
+        self.max_keyboard_walk.get()
+    }
+
+    /// Sets the keyboard layouts on which walks are looked for.
+    /// All the built-in layouts are used by default.
+    pub fn set_keyboard_layouts(&self, layouts: Vec<KeyboardLayout>) {
+        *self.keyboard_layouts.borrow_mut() = layouts;
+    }
+
+    /// Returns the names of the keyboard layouts on which walks are looked for.
+    pub fn get_keyboard_layouts(&self) -> Vec<&'static str> {
+        self.keyboard_layouts.borrow().iter().map(KeyboardLayout::name).collect()
+    }
+
     /// Sets whether the check for the presence of words longer than 3 characters present in the
     /// `passwd` GECOS field of a user in a password is enabled.
     pub fn set_gecos_check(&self, check: bool) {
@@ -312,6 +446,37 @@ impl PWQuality {
         }
     }
 
+    /// Sets the source of the GECOS field of users, checked instead of the field of the system
+    /// password database when the GECOS check is enabled.
+    /// The field is looked up with the username given to the check, unless an explicit one is
+    /// given in the `UserContext`.
+    pub fn set_gecos_provider<P: GecosProvider + 'static>(&self, provider: P) {
+        self.gecos_provider.borrow_mut().0 = Some(Box::new(provider));
+    }
+
+    /// Removes the source of the GECOS field of users.
+    pub fn clear_gecos_provider(&self) {
+        self.gecos_provider.borrow_mut().0 = None;
+    }
+
+    /// Returns whether a source of the GECOS field of users is set.
+    pub fn has_gecos_provider(&self) -> bool {
+        self.gecos_provider.borrow().0.is_some()
+    }
+
+    /// Sets the Unicode handling of the checks, see the `unicode` module.
+    /// A value of `None` leaves the passwords to the byte oriented checks of the library.
+    #[cfg(feature = "unicode")]
+    pub fn set_unicode_options(&self, options: Option<unicode::UnicodeOptions>) {
+        self.unicode_options.set(options);
+    }
+
+    /// Returns the Unicode handling of the checks, if any.
+    #[cfg(feature = "unicode")]
+    pub fn get_unicode_options(&self) -> Option<unicode::UnicodeOptions> {
+        self.unicode_options.get()
+    }
+
     /// Sets whether the check that a password is contained in a dictionary is enabled.
     pub fn set_dictionary_check(&self, check: bool) {
         let value = if check { 1 } else { 0 };
@@ -369,6 +534,64 @@ impl PWQuality {
         }
     }
 
+    /// Sets the number of times the user is prompted for a new password
+    /// by the PAM module before failing.
+    pub fn set_retry_times(&self, times: i32) {
+        unsafe {
+            let res = pwquality_set_int_value(self.pwq, PWQ_SETTING_RETRY_TIMES, times);
+            assert!(res == 0);
+        }
+    }
+
+    /// Returns the number of times the user is prompted for a new password
+    /// by the PAM module before failing.
+    pub fn get_retry_times(&self) -> i32 {
+        unsafe {
+            let result: *mut i32 = &mut 0;
+            let res = pwquality_get_int_value(self.pwq, PWQ_SETTING_RETRY_TIMES, result);
+            assert!(res == 0);
+            *result
+        }
+    }
+
+    /// Sets whether the checks are enforced when the password is changed by root.
+    pub fn set_enforce_for_root(&self, enforce: bool) {
+        let value = if enforce { 1 } else { 0 };
+        unsafe {
+            let res = pwquality_set_int_value(self.pwq, PWQ_SETTING_ENFORCE_ROOT, value);
+            assert!(res == 0);
+        }
+    }
+
+    /// Returns whether the checks are enforced when the password is changed by root.
+    pub fn get_enforce_for_root(&self) -> bool {
+        unsafe {
+            let result: *mut i32 = &mut 0;
+            let res = pwquality_get_int_value(self.pwq, PWQ_SETTING_ENFORCE_ROOT, result);
+            assert!(res == 0);
+            *result != 0
+        }
+    }
+
+    /// Sets whether the checks are skipped for users not present in `/etc/passwd`.
+    pub fn set_local_users_only(&self, local: bool) {
+        let value = if local { 1 } else { 0 };
+        unsafe {
+            let res = pwquality_set_int_value(self.pwq, PWQ_SETTING_LOCAL_USERS, value);
+            assert!(res == 0);
+        }
+    }
+
+    /// Returns whether the checks are skipped for users not present in `/etc/passwd`.
+    pub fn get_local_users_only(&self) -> bool {
+        unsafe {
+            let result: *mut i32 = &mut 0;
+            let res = pwquality_get_int_value(self.pwq, PWQ_SETTING_LOCAL_USERS, result);
+            assert!(res == 0);
+            *result != 0
+        }
+    }
+
     /// Sets the path to the dictionary to use (other than the default cracklib one).
     pub fn set_dictionary_path(&self, path: String) -> Result<(), Error>{
         let c_path = CString::new(path.as_str()).unwrap();
@@ -434,6 +657,105 @@ impl PWQuality {
         }
     }
 
+    /// Sets the forbidden words matched through leetspeak and case changes, in addition to the
+    /// bad words of the library. A value of `None` disables the check.
+    pub fn set_bad_word_list(&self, list: Option<BadWordList>) {
+        *self.bad_word_list.borrow_mut() = list;
+    }
+
+    /// Returns whether forbidden words are matched through leetspeak and case changes.
+    pub fn has_bad_word_list(&self) -> bool {
+        self.bad_word_list.borrow().is_some()
+    }
+
+    /// Sets the large list of forbidden words and phrases, in addition to the bad words of the
+    /// library. A value of `None` disables the check.
+    pub fn set_bad_phrase_list(&self, list: Option<BadPhraseList>) {
+        *self.bad_phrase_list.borrow_mut() = list;
+    }
+
+    /// Reads the large list of forbidden words and phrases from the given file, one per line,
+    /// blank lines and lines starting with `#` being ignored.
+    /// It replaces the list previously read, if any.
+    /// Returns a `CfgfileOpen` error if the file does not exist, and an `Io` error if it
+    /// cannot be read.
+    pub fn load_bad_words_file<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let list = BadPhraseList::open(path).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => Error::CfgfileOpen,
+            _ => Error::Io { message: e.to_string() },
+        })?;
+        self.set_bad_phrase_list(Some(list));
+        Ok(())
+    }
+
+    /// Returns whether a large list of forbidden words and phrases is used to check passwords.
+    pub fn has_bad_phrase_list(&self) -> bool {
+        self.bad_phrase_list.borrow().is_some()
+    }
+
+    /// Sets the breached passwords list against which passwords are checked.
+    /// A value of `None` disables the check.
+    pub fn set_breach_database(&self, database: Option<BreachDatabase>) {
+        *self.breach.borrow_mut() = database;
+    }
+
+    /// Returns whether a breached passwords list is used to check passwords.
+    pub fn has_breach_database(&self) -> bool {
+        self.breach.borrow().is_some()
+    }
+
+    /// Sets the minimum number of occurrences in the breached passwords list
+    /// for a password to be rejected.
+    /// Any number less than `1` will be replaced by `1`.
+    pub fn set_breach_threshold(&self, min: u64) {
+        self.breach_threshold.set(min.max(1));
+    }
+
+    /// Returns the minimum number of occurrences in the breached passwords list
+    /// for a password to be rejected.
+    pub fn get_breach_threshold(&self) -> u64 {
+        self.breach_threshold.get()
+    }
+
+    /// Registers a custom rule, run after the library checks and the previously
+    /// registered rules.
+    pub fn add_rule<R: Rule + 'static>(&self, rule: R) {
+        self.rules.borrow_mut().0.push(Box::new(rule));
+    }
+
+    /// Removes all the registered custom rules.
+    pub fn clear_rules(&self) {
+        self.rules.borrow_mut().0.clear();
+    }
+
+    /// Sets the estimator used to compute the number of guesses in check reports.
+    pub fn set_estimator(&self, estimator: Estimator) {
+        *self.estimator.borrow_mut() = estimator;
+    }
+
+    /// Returns the values of the settings interpolated in the error messages.
+    pub fn message_params(&self) -> MessageParams {
+        let positive = |value: i32| if value > 0 { Some(value) } else { None };
+        MessageParams {
+            min_length: positive(self.get_min_length()),
+            min_digits: positive(-self.get_digit_credit()),
+            min_uppers: positive(-self.get_uppercase_credit()),
+            min_lowers: positive(-self.get_lowercase_credit()),
+            min_others: positive(-self.get_other_credit()),
+            min_classes: positive(self.get_min_classes()),
+            max_repeat: positive(self.get_max_repeat()),
+            max_class_repeat: positive(self.get_max_class_repeat()),
+            max_sequence: positive(self.get_max_sequence()),
+            max_keyboard_walk: positive(self.get_max_keyboard_walk()),
+        }
+    }
+
+    /// Returns the message describing the error in the language of the given locale,
+    /// with the values of the settings of this instance.
+    pub fn error_message(&self, error: &Error, locale: &str) -> String {
+        error.message_with(locale, &self.message_params())
+    }
+
     /// Generates a password with the given number of bits of entropy.
     pub fn generate_password(&self, entropy: i32) -> Result<String, Error> {
         let (res, ptr) =
@@ -452,34 +774,514 @@ impl PWQuality {
         }
     }
 
+    /// Generates a password with the given number of bits of entropy, drawing randomness from
+    /// the given generator instead of the one of the library.
+    /// The password is made of printable ASCII characters picked uniformly, and the entropy is
+    /// clamped between `generator::MIN_ENTROPY_BITS` and `generator::MAX_ENTROPY_BITS` like the library does.
+    pub fn generate_password_with_rng<R: RngCore + CryptoRng>(&self, rng: &mut R, entropy: i32) -> String {
+        generator::random_password(rng, entropy)
+    }
+
+    /// Generates a password with the given number of bits of entropy, that passes the checks.
+    /// The generated password is completed with characters inserted at random positions to
+    /// satisfy the minimum length, credits and classes settings. A new password is generated if
+    /// it still does not pass the checks, up to `MAX_COMPLIANT_ATTEMPTS` times, after which an
+    /// `Unsatisfiable` error reporting the rejections is returned.
+    /// Randomness is drawn from the operating system.
+    pub fn generate_compliant(&self, entropy: i32) -> Result<String, Error> {
+        self.generate_compliant_with_rng(&mut OsRng, entropy)
+    }
+
+    /// Same as `generate_compliant`, drawing randomness from the given generator.
+    pub fn generate_compliant_with_rng<R: RngCore + CryptoRng>(&self, rng: &mut R, entropy: i32) -> Result<String, Error> {
+        let requirements = Requirements::from_settings(self);
+        let mut rejections: Vec<Error> = Vec::new();
+        for _ in 0..MAX_COMPLIANT_ATTEMPTS {
+            let generated = generator::random_password(rng, entropy);
+            let password = generator::repair(rng, &generated, &requirements);
+            match self.check(password.clone(), None, None) {
+                Ok(_) => return Ok(password),
+                Err(e) => {
+                    if !rejections.contains(&e) {
+                        rejections.push(e);
+                    }
+                },
+            }
+        }
+        let reason = format!("no generated password passed the checks in {} attempts, rejected with: {:?}",
+                             MAX_COMPLIANT_ATTEMPTS, rejections);
+        Err(Error::Unsatisfiable { reason })
+    }
+
+    /// Generates a passphrase made of words picked at random, that passes the checks.
+    /// Uppercase letters, digits and symbols are added as needed to satisfy the credit and
+    /// classes settings, and the passphrase is generated again if it does not pass the checks,
+    /// up to the maximum number of attempts of the options.
+    /// Randomness is drawn from the operating system.
+    pub fn generate_passphrase(&self, options: &PassphraseOptions) -> Result<Passphrase, Error> {
+        self.generate_passphrase_with_rng(&mut OsRng, options)
+    }
+
+    /// Same as `generate_passphrase`, drawing randomness from the given generator.
+    pub fn generate_passphrase_with_rng<R: RngCore + CryptoRng>(&self, rng: &mut R, options: &PassphraseOptions) -> Result<Passphrase, Error> {
+        if options.word_list.len() < 2 {
+            return Err(Error::GenerationFailed);
+        }
+        let requirements = Requirements::from_settings(self);
+        let mut error = Error::GenerationFailed;
+        for _ in 0..options.max_attempts.max(1) {
+            let passphrase = passphrase::generate(rng, options, &requirements);
+            match self.check(passphrase.as_str().to_owned(), None, None) {
+                Ok(_) => return Ok(passphrase),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+
+    /// Generates a pronounceable password made of syllables of the model of the options, that
+    /// passes the checks.
+    /// Uppercase letters, digits and symbols are added as needed to satisfy the credit and
+    /// classes settings, and the password is generated again if it does not pass the checks,
+    /// up to the maximum number of attempts of the options.
+    /// Randomness is drawn from the operating system.
+    pub fn generate_pronounceable(&self, options: &PronounceableOptions) -> Result<Pronounceable, Error> {
+        self.generate_pronounceable_with_rng(&mut OsRng, options)
+    }
+
+    /// Same as `generate_pronounceable`, drawing randomness from the given generator.
+    pub fn generate_pronounceable_with_rng<R: RngCore + CryptoRng>(&self, rng: &mut R, options: &PronounceableOptions) -> Result<Pronounceable, Error> {
+        if !options.model.is_usable() {
+            return Err(Error::GenerationFailed);
+        }
+        let requirements = Requirements::from_settings(self);
+        let mut error = Error::GenerationFailed;
+        for _ in 0..options.max_attempts.max(1) {
+            let password = pronounceable::generate(rng, options, &requirements);
+            match self.check(password.as_str().to_owned(), None, None) {
+                Ok(_) => return Ok(password),
+                Err(e) => error = e,
+            }
+        }
+        Err(error)
+    }
+
+    /// Generates a password following the given specification, that passes the checks.
+    /// An `Unsatisfiable` error is returned if the specification cannot meet the settings,
+    /// or if no generated password passed the checks within the maximum number of attempts
+    /// of the specification.
+    /// Randomness is drawn from the operating system.
+    pub fn generate_from_spec(&self, spec: &GeneratorSpec) -> Result<String, Error> {
+        self.generate_from_spec_with_rng(&mut OsRng, spec)
+    }
+
+    /// Same as `generate_from_spec`, drawing randomness from the given generator.
+    pub fn generate_from_spec_with_rng<R: RngCore + CryptoRng>(&self, rng: &mut R, spec: &GeneratorSpec) -> Result<String, Error> {
+        let requirements = Requirements::from_settings(self);
+        spec.validate(&requirements)?;
+        let mut error = None;
+        for _ in 0..spec.max_attempts.max(1) {
+            let password = spec.generate(rng, &requirements)?;
+            match self.check(password.clone(), None, None) {
+                Ok(_) => return Ok(password),
+                Err(e) => error = Some(e),
+            }
+        }
+        let reason = format!("no generated password passed the checks in {} attempts, last error: {:?}",
+                             spec.max_attempts.max(1), error);
+        Err(Error::Unsatisfiable { reason })
+    }
+
     /// Checks a password according to the settings and returns the computed score.
     pub fn check(&self, password: String, old_password: Option<String>, username: Option<String>) -> Result<i32, Error> {
-        unsafe {
-            let c_password = CString::new(password).unwrap();
-            let res =
-                match (old_password, username) {
-                    (Some(old_password), Some(username)) => {
-                        let c_old_password = CString::new(old_password).unwrap();
-                        let c_user = CString::new(username).unwrap();
-                        pwquality_check(self.pwq, c_password.as_ptr(), c_old_password.as_ptr(), c_user.as_ptr(), null_mut())
-                    },
-                    (Some(old_password), None) => {
-                        let c_old_password = CString::new(old_password).unwrap();
-                        pwquality_check(self.pwq, c_password.as_ptr(), c_old_password.as_ptr(), null(), null_mut())
-                    },
-                    (None, Some(username)) => {
-                        let c_user = CString::new(username).unwrap();
-                        pwquality_check(self.pwq, c_password.as_ptr(), null(), c_user.as_ptr(), null_mut())
-                    },
-                    (None, None) =>
-                        pwquality_check(self.pwq, c_password.as_ptr(), null(), null(), null_mut())
-                };
-            if res < 0 {
-                Err(Error::from_int(res))
+        let ctx = CheckContext::new(&password, old_password.as_deref(), username.as_deref(), None);
+        self.run_checks(ctx)
+    }
+
+    /// Checks a password according to the settings and returns the computed score.
+    /// The password must additionally not reuse, or be derived from, a password of the history,
+    /// otherwise a `History` error is returned. The history is only checked if the password
+    /// passed the other checks, as hashing is slow.
+    pub fn check_with_history(&self, password: String, username: Option<String>, history: &PasswordHistory) -> Result<i32, Error> {
+        let score = self.check(password.clone(), None, username)?;
+        history.check(&password)?;
+        Ok(score)
+    }
+
+    /// Checks a password according to the settings and returns the computed score.
+    /// The password is additionally compared by the library to each of the previous passwords,
+    /// as it is to the old password by `check`, and a `History` error giving the index of the
+    /// first previous password it is too similar to is returned, with the reason reported by
+    /// the library (e.g. `TooSimilar`, `Rotated` or `CaseChangesOnly`).
+    /// As for `check`, the similarity is only checked if the minimum number of changes
+    /// (see `get_min_diff`) is set, reusing a previous password is always rejected.
+    pub fn check_against_history(&self, password: String, old_passwords: &[String]) -> Result<i32, Error> {
+        let score = self.check(password.clone(), None, None)?;
+        // the password passed the checks on its own, so a failure comes from the previous password
+        for (index, old_password) in old_passwords.iter().enumerate() {
+            if let Err(reason) = self.check_library(&password, Some(old_password), None) {
+                return Err(Error::History { index, reason: Box::new(reason) });
+            }
+        }
+        Ok(score)
+    }
+
+    /// Checks a password according to the settings and the attributes of the user,
+    /// and returns the computed score.
+    /// The username is checked by the library, and words derived from the other attributes
+    /// may not appear in the password, as for the GECOS check.
+    pub fn check_with_context(&self, password: String, old_password: Option<String>, context: &UserContext) -> Result<i32, Error> {
+        let ctx = CheckContext::new(&password, old_password.as_deref(), context.username.as_deref(), Some(context));
+        self.run_checks(ctx)
+    }
+
+    /// Checks the new password of a system user like the pam_pwquality module does, when it
+    /// is changed by a process with the effective uid of the current one.
+    /// See `check_for_user_as`.
+    pub fn check_for_user(&self, password: String, old_password: Option<String>, username: &str) -> Result<UserCheck, Error> {
+        let uid = unsafe { libc::geteuid() };
+        self.check_for_user_as(password, old_password, username, uid)
+    }
+
+    /// Checks the new password of a system user like the pam_pwquality module does, when it
+    /// is changed by a process with the given uid.
+    /// The checks are skipped if `local_users_only` is set and the user is not listed in
+    /// `/etc/passwd`. Otherwise the user is looked up in the system databases, and an
+    /// `UnknownUser` error is returned if it does not exist, or an `Io` error if the lookup
+    /// failed. A rejected password is accepted anyway if it is changed by root and
+    /// `enforce_for_root` is not set, or if `enforcing` is not set; the returned check reports
+    /// whether enforcement was bypassed.
+    pub fn check_for_user_as(&self, password: String, old_password: Option<String>, username: &str, uid: libc::uid_t) -> Result<UserCheck, Error> {
+        if self.get_local_users_only() && !users::is_local(username) {
+            return Ok(UserCheck::skipped(Bypass::NonLocalUser));
+        }
+        let user = match users::getpwnam(username) {
+            Ok(Some(user)) => user,
+            Ok(None) => return Err(Error::UnknownUser { user: username.to_owned() }),
+            // a name with a nul byte cannot be the one of a user
+            Err(ref e) if e.kind() == io::ErrorKind::InvalidInput => return Err(Error::UnknownUser { user: username.to_owned() }),
+            Err(e) => return Err(Error::Io { message: e.to_string() }),
+        };
+        let bypass = if uid == 0 && !self.get_enforce_for_root() {
+            Some(Bypass::Root)
+        } else if !self.get_enforcing() {
+            Some(Bypass::NotEnforcing)
+        } else {
+            None
+        };
+        let result = self.check(password, old_password, Some(user.name));
+        Ok(UserCheck::checked(result, bypass))
+    }
+
+    /// Checks a password according to the settings and returns a detailed report,
+    /// including an estimation of the number of guesses needed to find it.
+    pub fn check_report(&self, password: String, old_password: Option<String>, username: Option<String>) -> CheckReport {
+        let ctx = CheckContext::new(&password, old_password.as_deref(), username.as_deref(), None);
+        self.report(ctx)
+    }
+
+    /// Checks a password according to the settings and the attributes of the user,
+    /// and returns a detailed report, including an estimation of the number of guesses
+    /// needed to find it.
+    pub fn check_report_with_context(&self, password: String, old_password: Option<String>, context: &UserContext) -> CheckReport {
+        let ctx = CheckContext::new(&password, old_password.as_deref(), context.username.as_deref(), Some(context));
+        self.report(ctx)
+    }
+
+    /// Checks a password according to the settings, without stopping at the first failed check,
+    /// and returns all the violations found.
+    /// See `FullCheck` for the checks of the library that hide the other ones.
+    pub fn check_all(&self, password: String, old_password: Option<String>, username: Option<String>) -> FullCheck {
+        let ctx = CheckContext::new(&password, old_password.as_deref(), username.as_deref(), None);
+        self.run_all_checks(ctx)
+    }
+
+    /// Checks a password according to the settings and the attributes of the user, without
+    /// stopping at the first failed check, and returns all the violations found.
+    pub fn check_all_with_context(&self, password: String, old_password: Option<String>, context: &UserContext) -> FullCheck {
+        let ctx = CheckContext::new(&password, old_password.as_deref(), context.username.as_deref(), Some(context));
+        self.run_all_checks(ctx)
+    }
+
+    fn report(&self, ctx: CheckContext) -> CheckReport {
+        let mut user_inputs: Vec<String> = ctx.username().into_iter().map(String::from).collect();
+        if let Some(user) = ctx.user_context() {
+            user_inputs.extend(user.forbidden_words());
+        }
+        let user_inputs: Vec<&str> = user_inputs.iter().map(String::as_str).collect();
+        let estimate = self.estimator.borrow().estimate(ctx.password(), &user_inputs);
+        let result = self.run_checks(ctx);
+        let suggestions = suggestion::suggest(self, &ctx, result.as_ref().err());
+        CheckReport::new(result, estimate, suggestions)
+    }
+
+    fn run_checks(&self, ctx: CheckContext) -> Result<i32, Error> {
+        #[cfg(feature = "unicode")]
+        {
+            if let Some(options) = self.unicode_options.get() {
+                return unicode::check(self, ctx, &options);
+            }
+        }
+        let score = self.check_library(ctx.password(), ctx.old_password(), ctx.username())?;
+        self.run_additional_checks(ctx, score)
+    }
+
+    // the checks of this crate, run after the checks of the library
+    fn run_additional_checks(&self, ctx: CheckContext, score: i32) -> Result<i32, Error> {
+        if let Some(user) = ctx.user_context() {
+            if user.find_forbidden_word(ctx.password()).is_some() {
+                return Err(Error::GecosCheck);
+            }
+        }
+        self.check_gecos(&ctx)?;
+        self.check_bad_word_list(ctx.password())?;
+        self.check_keyboard_walks(ctx.password())?;
+        self.check_breached(ctx.password())?;
+        self.check_rules(ctx.with_score(score))
+    }
+
+    fn run_all_checks(&self, ctx: CheckContext) -> FullCheck {
+        #[cfg(feature = "unicode")]
+        {
+            if let Some(options) = self.unicode_options.get() {
+                return unicode::check_all(self, ctx, &options);
+            }
+        }
+        let mut violations = Vec::new();
+        let score = self.check_library_all(ctx.password(), ctx.old_password(), ctx.username(), &mut violations);
+        self.run_all_additional_checks(ctx, score, violations)
+    }
+
+    // the checks of this crate, run after the checks of the library, reporting all the failed ones
+    fn run_all_additional_checks(&self, ctx: CheckContext, score: Option<i32>, mut violations: Vec<Error>) -> FullCheck {
+        if let Some(user) = ctx.user_context() {
+            if user.find_forbidden_word(ctx.password()).is_some() {
+                violations.push(Error::GecosCheck);
+            }
+        }
+        let results = vec![
+            self.check_gecos(&ctx),
+            self.check_bad_word_list(ctx.password()),
+            self.check_keyboard_walks(ctx.password()),
+            self.check_breached(ctx.password()),
+        ];
+        for error in results.into_iter().filter_map(Result::err) {
+            if !violations.contains(&error) {
+                violations.push(error);
+            }
+        }
+        let mut score = score.unwrap_or(0);
+        for rule in self.rules.borrow().0.iter() {
+            match rule.check(&ctx.with_score(score)) {
+                RuleOutcome::Pass => (),
+                RuleOutcome::Adjust(delta) => score = score.saturating_add(delta).clamp(0, 100),
+                RuleOutcome::Reject { code, message } => violations.push(Error::Custom { code, message }),
+            }
+        }
+        let penalties = suggestion::penalties(self, &ctx, &violations);
+        FullCheck::new(violations, penalties, score)
+    }
+
+    // the length and class checks of the library, reporting all the failed ones
+    fn check_length_and_classes(&self, password: &str, violations: &mut Vec<Error>) {
+        let count = |class: fn(&u8) -> bool| password.bytes().filter(class).count() as i32;
+        let other = |c: &u8| !c.is_ascii_alphanumeric();
+        let credits = [
+            (count(u8::is_ascii_digit), self.get_digit_credit(), Error::MinDigits),
+            (count(u8::is_ascii_uppercase), self.get_uppercase_credit(), Error::MinUppers),
+            (count(u8::is_ascii_lowercase), self.get_lowercase_credit(), Error::MinLowers),
+            (count(other), self.get_other_credit(), Error::MinOthers),
+        ];
+        let mut size = self.get_min_length();
+        for &(count, credit, ref error) in &credits {
+            if credit >= 0 {
+                size -= count.min(credit);
+            } else if count < -credit {
+                violations.push(error.clone());
+            }
+        }
+        if (password.len() as i32) < size {
+            violations.push(Error::MinLength);
+        }
+        let present = credits.iter().filter(|&&(count, _, _)| count > 0).count() as i32;
+        if present < self.get_min_classes() {
+            violations.push(Error::MinClasses);
+        }
+    }
+
+    // runs the library checks on a copy of the settings, disabling each failed check
+    // and running them again, until the password passes or a check cannot be disabled
+    fn check_library_all(&self, password: &str, old_password: Option<&str>, username: Option<&str>, violations: &mut Vec<Error>) -> Option<i32> {
+        self.check_length_and_classes(password, violations);
+        self.library_copy().check_library_relaxing(password, old_password, username, violations)
+    }
+
+    // runs the library checks, disabling each failed check in these settings and running them
+    // again, the length and classes being checked beforehand
+    fn check_library_relaxing(&self, password: &str, old_password: Option<&str>, username: Option<&str>, violations: &mut Vec<Error>) -> Option<i32> {
+        let mut old_password = old_password;
+        let mut failed = Vec::new();
+        loop {
+            let error = match self.check_library(password, old_password, username) {
+                Ok(score) => return Some(score),
+                Err(error) => error,
+            };
+            // a disabled check cannot fail again, this is a safeguard against looping
+            if failed.contains(&error) {
+                return None;
+            }
+            failed.push(error.clone());
+            match error {
+                Error::MinDigits => self.set_digit_credit(0),
+                Error::MinUppers => self.set_uppercase_credit(0),
+                Error::MinLowers => self.set_lowercase_credit(0),
+                Error::MinOthers => self.set_other_credit(0),
+                Error::MinClasses => self.set_min_classes(0),
+                // the library does not accept a lower minimum length, a shorter password fails again
+                Error::MinLength => self.set_min_length(LIBRARY_MIN_LENGTH),
+                Error::MaxConsecutive => self.set_max_repeat(0),
+                Error::MaxClassRepeat => self.set_max_class_repeat(0),
+                Error::MaxSequence => self.set_max_sequence(0),
+                Error::CracklibCheck => self.set_dictionary_check(false),
+                Error::UserCheck => self.set_user_check(false),
+                Error::GecosCheck => self.set_gecos_check(false),
+                Error::BadWords { .. } => self.set_bad_words(Vec::new()).unwrap_or(()),
+                // the comparisons with the old password are skipped without it
+                Error::TooSimilar | Error::SamePassword | Error::CaseChangesOnly | Error::Rotated
+                    if old_password.is_some() => old_password = None,
+                _ => {
+                    violations.push(error);
+                    return None;
+                },
+            }
+            // the length and the classes are already checked
+            if !matches!(error, Error::MinDigits | Error::MinUppers | Error::MinLowers | Error::MinOthers
+                    | Error::MinClasses | Error::MinLength) {
+                violations.push(error);
+            }
+        }
+    }
+
+    // a new instance with the same library settings
+    fn library_copy(&self) -> PWQuality {
+        let copy = PWQuality::new();
+        copy.set_min_diff(self.get_min_diff());
+        copy.set_min_length(self.get_min_length());
+        copy.set_digit_credit(self.get_digit_credit());
+        copy.set_uppercase_credit(self.get_uppercase_credit());
+        copy.set_lowercase_credit(self.get_lowercase_credit());
+        copy.set_other_credit(self.get_other_credit());
+        copy.set_min_classes(self.get_min_classes());
+        copy.set_max_repeat(self.get_max_repeat());
+        copy.set_max_class_repeat(self.get_max_class_repeat());
+        copy.set_max_sequence(self.get_max_sequence());
+        // the GECOS field of the provider replaces the one of the system password database
+        copy.set_gecos_check(self.get_gecos_check() && !self.has_gecos_provider());
+        copy.set_dictionary_check(self.get_dictionary_check());
+        copy.set_user_check(self.get_user_check());
+        if let Ok(Some(path)) = self.get_dictionary_path() {
+            let _ = copy.set_dictionary_path(path);
+        }
+        if let Ok(words) = self.get_bad_words() {
+            let _ = copy.set_bad_words(words);
+        }
+        copy
+    }
+
+    fn check_gecos(&self, ctx: &CheckContext) -> Result<(), Error> {
+        match self.gecos_word(ctx) {
+            Some(_) => Err(Error::GecosCheck),
+            None => Ok(()),
+        }
+    }
+
+    // the word of the GECOS field given by the provider found in the password, if any
+    fn gecos_word(&self, ctx: &CheckContext) -> Option<String> {
+        if !self.get_gecos_check() {
+            return None;
+        }
+        // an explicit GECOS field is checked with the other attributes of the user
+        if ctx.user_context().map(|user| user.gecos.is_some()).unwrap_or(false) {
+            return None;
+        }
+        let provider = self.gecos_provider.borrow();
+        let field = provider.0.as_ref().and_then(|provider| ctx.username().and_then(|username| provider.gecos(username)))?;
+        gecos::find_gecos_word(ctx.password(), &field)
+    }
+
+    fn check_bad_word_list(&self, password: &str) -> Result<(), Error> {
+        let word = self.bad_word_list.borrow().as_ref().and_then(|list| list.find(password).map(str::to_owned))
+            .or_else(|| self.bad_phrase_list.borrow().as_ref().and_then(|list| list.find(password).map(str::to_owned)));
+        match word {
+            Some(word) => Err(Error::BadWords { word: Some(word) }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_keyboard_walks(&self, password: &str) -> Result<(), Error> {
+        let max = self.max_keyboard_walk.get() as usize;
+        if max > 0 {
+            for layout in self.keyboard_layouts.borrow().iter() {
+                if layout.longest_walk(password).map(|walk| walk.len() > max).unwrap_or(false) {
+                    return Err(Error::KeyboardWalk);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_library(&self, password: &str, old_password: Option<&str>, username: Option<&str>) -> Result<i32, Error> {
+        let c_password = CString::new(password).unwrap();
+        let c_old_password = old_password.map(|old_password| CString::new(old_password).unwrap());
+        let c_user = username.map(|username| CString::new(username).unwrap());
+        let res = {
+            // the GECOS field of the provider replaces the one of the system password database
+            let _gecos_off = if self.has_gecos_provider() && self.get_gecos_check() {
+                Some(GecosCheckOff::new(self))
             } else {
-                Ok(res)
+                None
+            };
+            unsafe {
+                pwquality_check(self.pwq, c_password.as_ptr(),
+                                c_old_password.as_ref().map_or(null(), |old_password| old_password.as_ptr()),
+                                c_user.as_ref().map_or(null(), |user| user.as_ptr()),
+                                null_mut())
+            }
+        };
+        if res >= 0 {
+            return Ok(res);
+        }
+        match Error::from_int(res) {
+            // the library does not tell which of its bad words was found
+            Error::BadWords { word: None } => {
+                let word = self.get_bad_words().ok()
+                    .and_then(|words| words.into_iter().find(|word| contains_word(password, word)));
+                Err(Error::BadWords { word })
+            },
+            error => Err(error),
+        }
+    }
+
+    fn check_breached(&self, password: &str) -> Result<(), Error> {
+        if let Some(ref database) = *self.breach.borrow() {
+            let count = database.occurrences(password);
+            if count >= self.breach_threshold.get() {
+                return Err(Error::Breached { count });
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rules(&self, ctx: CheckContext) -> Result<i32, Error> {
+        let mut score = ctx.score();
+        for rule in self.rules.borrow().0.iter() {
+            match rule.check(&ctx.with_score(score)) {
+                RuleOutcome::Pass => (),
+                RuleOutcome::Adjust(delta) => score = score.saturating_add(delta).clamp(0, 100),
+                RuleOutcome::Reject { code, message } => return Err(Error::Custom { code, message }),
             }
         }
+        Ok(score)
     }
 
 }
@@ -492,3 +1294,153 @@ impl Drop for PWQuality {
         }
     }
 }
+
+// turns the GECOS check of the library off until dropped, even when unwinding
+struct GecosCheckOff<'a>(&'a PWQuality);
+
+impl<'a> GecosCheckOff<'a> {
+
+    fn new(pwq: &'a PWQuality) -> Self {
+        pwq.set_gecos_check(false);
+        GecosCheckOff(pwq)
+    }
+
+}
+
+impl<'a> Drop for GecosCheckOff<'a> {
+    fn drop(&mut self) {
+        self.0.set_gecos_check(true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "Wq-7-Zt!x-Vb4";
+
+    fn history(old_passwords: &[&str]) -> Vec<String> {
+        old_passwords.iter().map(|&p| p.to_owned()).collect()
+    }
+
+    #[test]
+    fn empty_history() {
+        let pwq = PWQuality::new();
+        pwq.set_min_diff(Some(5));
+        assert_eq!(pwq.check_against_history(PASSWORD.to_owned(), &[]), pwq.check(PASSWORD.to_owned(), None, None));
+        assert!(pwq.check_against_history(PASSWORD.to_owned(), &[]).is_ok());
+    }
+
+    #[test]
+    fn history_reasons() {
+        let pwq = PWQuality::new();
+        pwq.set_min_diff(Some(5));
+        let cases = [
+            (PASSWORD, Error::SamePassword),
+            ("wq-7-ZT!X-vb4", Error::CaseChangesOnly),
+            ("Wq-7-Zt!x-Vb5", Error::TooSimilar),
+            ("Vb4Wq-7-Zt!x-", Error::Rotated),
+        ];
+        for (old_password, reason) in cases {
+            // the first previous password the new one is too similar to is reported
+            let old_passwords = history(&["Unrelated-99x", old_password, PASSWORD]);
+            assert_eq!(pwq.check_against_history(PASSWORD.to_owned(), &old_passwords),
+                       Err(Error::History { index: 1, reason: Box::new(reason) }), "{}", old_password);
+        }
+        assert!(pwq.check_against_history(PASSWORD.to_owned(), &history(&["Unrelated-99x", "Other-42-pass"])).is_ok());
+        // the password is checked on its own first
+        assert_eq!(pwq.check_against_history("abc".to_owned(), &history(&["abc"])), Err(Error::MinLength));
+    }
+
+    #[test]
+    fn history_min_diff() {
+        let pwq = PWQuality::new();
+        let old_passwords = history(&["Wq-7-Zt!x-Vb5"]);
+        pwq.set_min_diff(None);
+        assert!(pwq.check_against_history(PASSWORD.to_owned(), &old_passwords).is_ok());
+        pwq.set_min_diff(Some(1));
+        assert!(pwq.check_against_history(PASSWORD.to_owned(), &old_passwords).is_ok());
+        pwq.set_min_diff(Some(2));
+        assert_eq!(pwq.check_against_history(PASSWORD.to_owned(), &old_passwords),
+                   Err(Error::History { index: 0, reason: Box::new(Error::TooSimilar) }));
+        // reusing a password is rejected whatever the setting
+        pwq.set_min_diff(None);
+        assert_eq!(pwq.check_against_history(PASSWORD.to_owned(), &history(&[PASSWORD])),
+                   Err(Error::History { index: 0, reason: Box::new(Error::SamePassword) }));
+    }
+
+    fn enforcing() -> PWQuality {
+        let pwq = PWQuality::new();
+        pwq.set_enforcing(true);
+        pwq.set_enforce_for_root(false);
+        pwq.set_local_users_only(false);
+        pwq
+    }
+
+    #[test]
+    fn root_bypass() {
+        let pwq = enforcing();
+        let check = pwq.check_for_user_as("abc".to_owned(), None, "root", 0).unwrap();
+        assert!(check.is_accepted());
+        assert_eq!(check.bypass(), Some(Bypass::Root));
+        assert_eq!(check.error(), Some(&Error::MinLength));
+        // a user changing its own password is not bypassed
+        let check = pwq.check_for_user_as("abc".to_owned(), None, "root", 1000).unwrap();
+        assert!(!check.is_accepted());
+        assert_eq!(check.bypass(), None);
+        pwq.set_enforce_for_root(true);
+        let check = pwq.check_for_user_as("abc".to_owned(), None, "root", 0).unwrap();
+        assert!(!check.is_accepted());
+        assert_eq!(check.bypass(), None);
+        // accepted passwords are not bypassed
+        let check = enforcing().check_for_user_as(PASSWORD.to_owned(), None, "root", 0).unwrap();
+        assert!(check.is_accepted());
+        assert!(!check.is_bypassed());
+    }
+
+    #[test]
+    fn not_enforcing_bypass() {
+        let pwq = enforcing();
+        pwq.set_enforcing(false);
+        let check = pwq.check_for_user_as("abc".to_owned(), None, "root", 1000).unwrap();
+        assert!(check.is_accepted());
+        assert_eq!(check.bypass(), Some(Bypass::NotEnforcing));
+        assert_eq!(check.error(), Some(&Error::MinLength));
+    }
+
+    #[test]
+    fn non_local_user_bypass() {
+        let pwq = enforcing();
+        let user = "pwquality-no-such-user";
+        assert_eq!(pwq.check_for_user_as("abc".to_owned(), None, user, 1000).unwrap_err(),
+                   Error::UnknownUser { user: user.to_owned() });
+        assert_eq!(pwq.check_for_user_as("abc".to_owned(), None, "nul\0user", 1000).unwrap_err(),
+                   Error::UnknownUser { user: "nul\0user".to_owned() });
+        // the local password file is read before the system databases
+        pwq.set_local_users_only(true);
+        let check = pwq.check_for_user_as("abc".to_owned(), None, user, 1000).unwrap();
+        assert!(check.is_accepted());
+        assert_eq!(check.bypass(), Some(Bypass::NonLocalUser));
+        assert_eq!(check.result(), None);
+        // root is always in the local password file
+        let check = pwq.check_for_user_as("abc".to_owned(), None, "root", 1000).unwrap();
+        assert!(!check.is_accepted());
+    }
+
+    #[test]
+    fn gecos_check_restored() {
+        let pwq = PWQuality::new();
+        pwq.set_gecos_check(true);
+        pwq.set_gecos_provider(|_: &str| Some("John Smith".to_owned()));
+        assert!(pwq.check(PASSWORD.to_owned(), None, Some("root".to_owned())).is_ok());
+        assert!(pwq.get_gecos_check());
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _gecos_off = GecosCheckOff::new(&pwq);
+            assert!(!pwq.get_gecos_check());
+            panic!("check failed");
+        }));
+        assert!(result.is_err());
+        assert!(pwq.get_gecos_check());
+    }
+
+}
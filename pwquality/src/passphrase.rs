@@ -0,0 +1,387 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Generation of diceware-like passphrases.
+//!
+//! A passphrase is made of words picked uniformly at random in a word list, joined
+//! with a separator. Uppercase letters, digits and symbols are added when asked for,
+//! or when required by the settings of the instance, and the exact entropy of the
+//! generation is reported.
+//!
+//! The built-in word list is the [EFF large list](https://www.eff.org/dice) of 7776 words
+//! (about 12.9 bits per word), © Electronic Frontier Foundation, licensed under
+//! [CC BY 3.0 US](https://creativecommons.org/licenses/by/3.0/us/).
+//! Other lists can be loaded with `WordList::from_file`.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use requirements::Requirements;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The symbols that may be added to a passphrase.
+pub const SYMBOLS: &str = "!#$%&*+=?@^~";
+
+/// A list of distinct words to pick passphrase words from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WordList {
+    words: Vec<String>,
+}
+
+impl WordList {
+
+    /// Creates a word list from the given words, in lowercase, ignoring duplicates.
+    pub fn new(words: Vec<String>) -> Self {
+        let mut seen = HashSet::new();
+        let words = words.into_iter()
+            .map(|w| w.trim().to_lowercase())
+            .filter(|w| !w.is_empty() && seen.insert(w.clone()))
+            .collect();
+        WordList {
+            words
+        }
+    }
+
+    /// Returns the built-in list of 7776 english words, the EFF large list.
+    pub fn builtin() -> Self {
+        WordList::parse(include_str!("../data/eff_large_wordlist.txt"))
+    }
+
+    /// Parses a word list, with one word per line.
+    /// Lines in the diceware format, where the word is preceded by its dice roll
+    /// (e.g. `11111 abacus` in the EFF lists), are also accepted.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(text: &str) -> Self {
+        let words = text.lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'))
+            .map(|l| {
+                let mut parts = l.split_whitespace();
+                match (parts.next(), parts.next()) {
+                    (Some(roll), Some(word)) if roll.chars().all(|c| c.is_ascii_digit()) => word.to_owned(),
+                    _ => l.to_owned(),
+                }
+            })
+            .collect();
+        WordList::new(words)
+    }
+
+    /// Loads the word list at the given path.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(WordList::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Returns the number of words in the list.
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns whether the list is empty.
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns the words of the list.
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    /// Returns the entropy in bits of a word picked at random in this list.
+    pub fn entropy_per_word(&self) -> f64 {
+        (self.words.len() as f64).log2()
+    }
+
+}
+
+impl Default for WordList {
+    fn default() -> Self {
+        WordList::builtin()
+    }
+}
+
+/// The options of passphrase generation.
+#[derive(Clone, Debug)]
+pub struct PassphraseOptions {
+    /// The list to pick words from.
+    pub word_list: WordList,
+    /// The number of words in the passphrase.
+    /// More words are used when needed to meet the minimum length and the required
+    /// letters of the settings.
+    pub words: usize,
+    /// The separator between words.
+    pub separator: String,
+    /// Whether the first letter of every word is capitalized.
+    pub capitalize: bool,
+    /// The minimum number of digits to add to the passphrase.
+    pub digits: usize,
+    /// The minimum number of symbols to add to the passphrase.
+    pub symbols: usize,
+    /// The maximum number of passphrases generated before giving up when they do not
+    /// pass the checks.
+    pub max_attempts: usize,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        PassphraseOptions {
+            word_list: WordList::builtin(),
+            words: 6,
+            separator: "-".to_owned(),
+            capitalize: false,
+            digits: 0,
+            symbols: 0,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// A generated passphrase, together with the entropy of its generation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Passphrase {
+    passphrase: String,
+    entropy: f64,
+}
+
+impl Passphrase {
+
+    pub(crate) fn new(passphrase: String, entropy: f64) -> Self {
+        Passphrase {
+            passphrase,
+            entropy
+        }
+    }
+
+    /// Returns the passphrase.
+    pub fn as_str(&self) -> &str {
+        &self.passphrase
+    }
+
+    /// Returns the passphrase.
+    pub fn into_string(self) -> String {
+        self.passphrase
+    }
+
+    /// Returns the entropy in bits of the generation, i.e. the base 2 logarithm of the number
+    /// of equally likely passphrases that could have been generated with the same options.
+    pub fn entropy(&self) -> f64 {
+        self.entropy
+    }
+
+}
+
+pub(crate) fn log2_binomial(n: usize, k: usize) -> f64 {
+    (1..=k.min(n)).map(|i| ((n + 1 - i) as f64 / i as f64).log2()).sum()
+}
+
+// picks `count` distinct indices in `0..n`
+fn distinct_indices<R: Rng>(rng: &mut R, n: usize, count: usize) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..n).collect();
+    indices.shuffle(rng);
+    indices.truncate(count);
+    indices
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// the number of words needed to meet the length and letters requirements, whatever the
+// words picked
+fn word_count(options: &PassphraseOptions, requirements: &Requirements) -> usize {
+    let list = options.word_list.words();
+    let shortest = list.iter().map(|w| w.chars().count()).min().unwrap_or(0);
+    let separator = options.separator.chars().count();
+    // a capitalized word loses one lowercase letter
+    let capitalized = (options.capitalize || requirements.uppercase > 0 || requirements.min_classes > 1) as usize;
+    let lowercase = list.iter()
+        .map(|w| w.chars().filter(|c| c.is_lowercase()).count())
+        .min()
+        .unwrap_or(0)
+        .saturating_sub(capitalized);
+    let div_ceil = |a: usize, b: usize| if b == 0 { 0 } else { a.div_ceil(b) };
+    options.words
+        .max(1)
+        // each word gets at most one uppercase letter
+        .max(requirements.uppercase)
+        .max(div_ceil(requirements.lowercase, lowercase))
+        // n words and n - 1 separators
+        .max(div_ceil(requirements.min_length + separator, shortest + separator))
+}
+
+/// Generates a passphrase with the given options, adding what is needed to meet the requirements.
+pub(crate) fn generate<R: Rng>(rng: &mut R, options: &PassphraseOptions, requirements: &Requirements) -> Passphrase {
+    let list = options.word_list.words();
+    let n = word_count(options, requirements);
+    let mut words: Vec<String> = (0..n).map(|_| list[rng.gen_range(0..list.len())].clone()).collect();
+    let mut entropy = n as f64 * options.word_list.entropy_per_word();
+
+    let separator_symbols = if n > 1 { (n - 1) * options.separator.chars().filter(|c| !c.is_alphanumeric()).count() } else { 0 };
+    let separator_digits = if n > 1 { (n - 1) * options.separator.chars().filter(|c| c.is_ascii_digit()).count() } else { 0 };
+    let mut uppercase = if options.capitalize { 0 } else { requirements.uppercase.min(n) };
+    let mut digits = options.digits.max(requirements.digits.saturating_sub(separator_digits));
+    let mut symbols = options.symbols.max(requirements.others.saturating_sub(separator_symbols));
+    // add the missing classes, lowercase letters are always present
+    let classes = |uppercase: usize, digits: usize, symbols: usize| {
+        1 + (options.capitalize || uppercase > 0) as usize
+            + (digits + separator_digits > 0) as usize
+            + (symbols + separator_symbols > 0) as usize
+    };
+    while classes(uppercase, digits, symbols) < requirements.min_classes.min(4) {
+        if !options.capitalize && uppercase == 0 {
+            uppercase = 1;
+        } else if digits + separator_digits == 0 {
+            digits = 1;
+        } else {
+            symbols = 1;
+        }
+    }
+
+    if options.capitalize {
+        for word in &mut words {
+            *word = capitalize(word);
+        }
+    } else if uppercase > 0 {
+        for idx in distinct_indices(rng, n, uppercase) {
+            words[idx] = capitalize(&words[idx]);
+        }
+        entropy += log2_binomial(n, uppercase);
+    }
+    if digits > 0 {
+        let idx = rng.gen_range(0..n);
+        for _ in 0..digits {
+            words[idx].push(char::from(b'0' + rng.gen_range(0..10u8)));
+        }
+        entropy += (n as f64).log2() + digits as f64 * 10f64.log2();
+    }
+    if symbols > 0 {
+        let symbol_chars: Vec<char> = SYMBOLS.chars().collect();
+        let idx = rng.gen_range(0..n);
+        for _ in 0..symbols {
+            words[idx].push(symbol_chars[rng.gen_range(0..symbol_chars.len())]);
+        }
+        entropy += (n as f64).log2() + symbols as f64 * (symbol_chars.len() as f64).log2();
+    }
+    Passphrase::new(words.join(&options.separator), entropy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|&w| w.to_owned()).collect()
+    }
+
+    #[test]
+    fn word_lists() {
+        let list = WordList::new(words(&["Apple", "apple", " pear ", "", "APPLE"]));
+        assert_eq!(list.words(), &["apple".to_owned(), "pear".to_owned()][..]);
+        assert_eq!(list.entropy_per_word(), 1.0);
+
+        let list = WordList::parse("# comment\n11111\tabacus\n11112 abdomen\n\nzebra\n");
+        assert_eq!(list.words(), &["abacus".to_owned(), "abdomen".to_owned(), "zebra".to_owned()][..]);
+
+        let builtin = WordList::builtin();
+        assert_eq!(builtin.len(), 7776);
+        assert!((builtin.entropy_per_word() - 12.925).abs() < 1e-3);
+        assert_eq!(builtin.words()[0], "abacus");
+        assert_eq!(builtin.words()[7775], "zoom");
+    }
+
+    #[test]
+    fn entropy() {
+        let options = PassphraseOptions {
+            word_list: WordList::new(words(&["alpha", "bravo", "charlie", "delta"])),
+            words: 4,
+            ..PassphraseOptions::default()
+        };
+        let passphrase = generate(&mut StdRng::seed_from_u64(1), &options, &Requirements::default());
+        assert_eq!(passphrase.entropy(), 8.0);
+        assert_eq!(passphrase.as_str().split('-').count(), 4);
+
+        let options = PassphraseOptions {
+            digits: 2,
+            ..options
+        };
+        let requirements = Requirements {
+            uppercase: 1,
+            ..Requirements::default()
+        };
+        let passphrase = generate(&mut StdRng::seed_from_u64(1), &options, &requirements);
+        let expected = 8.0 + log2_binomial(4, 1) + 2.0 + 2.0 * 10f64.log2();
+        assert!((passphrase.entropy() - expected).abs() < 1e-9);
+        assert_eq!(passphrase.as_str().chars().filter(char::is_ascii_uppercase).count(), 1);
+        assert_eq!(passphrase.as_str().chars().filter(char::is_ascii_digit).count(), 2);
+    }
+
+    #[test]
+    fn classes() {
+        let requirements = Requirements {
+            min_classes: 4,
+            ..Requirements::default()
+        };
+        let options = PassphraseOptions {
+            separator: "".to_owned(),
+            ..PassphraseOptions::default()
+        };
+        for seed in 0..20 {
+            let passphrase = generate(&mut StdRng::seed_from_u64(seed), &options, &requirements);
+            let passphrase = passphrase.as_str();
+            assert!(passphrase.chars().any(|c| c.is_ascii_uppercase()), "{}", passphrase);
+            assert!(passphrase.chars().any(|c| c.is_ascii_lowercase()), "{}", passphrase);
+            assert!(passphrase.chars().any(|c| c.is_ascii_digit()), "{}", passphrase);
+            assert!(passphrase.chars().any(|c| SYMBOLS.contains(c)), "{}", passphrase);
+        }
+    }
+
+    #[test]
+    fn word_count() {
+        let options = PassphraseOptions {
+            word_list: WordList::new(words(&["abc", "defgh"])),
+            words: 2,
+            ..PassphraseOptions::default()
+        };
+        let count = |requirements: Requirements| super::word_count(&options, &requirements);
+        assert_eq!(count(Requirements::default()), 2);
+        // three letters words and a separator: 4 * 4 - 1 characters
+        assert_eq!(count(Requirements { min_length: 15, ..Requirements::default() }), 4);
+        assert_eq!(count(Requirements { min_length: 16, ..Requirements::default() }), 5);
+        assert_eq!(count(Requirements { uppercase: 3, ..Requirements::default() }), 3);
+        // capitalized words keep two lowercase letters
+        assert_eq!(count(Requirements { lowercase: 7, ..Requirements::default() }), 3);
+        assert_eq!(count(Requirements { lowercase: 7, uppercase: 1, ..Requirements::default() }), 4);
+    }
+
+    #[test]
+    fn requirements() {
+        let requirements = Requirements {
+            min_length: 40,
+            lowercase: 30,
+            uppercase: 5,
+            ..Requirements::default()
+        };
+        let options = PassphraseOptions {
+            words: 3,
+            ..PassphraseOptions::default()
+        };
+        for seed in 0..20 {
+            let passphrase = generate(&mut StdRng::seed_from_u64(seed), &options, &requirements);
+            let passphrase = passphrase.as_str();
+            assert!(passphrase.chars().count() >= 40, "{}", passphrase);
+            assert!(passphrase.chars().filter(char::is_ascii_lowercase).count() >= 30, "{}", passphrase);
+            assert!(passphrase.chars().filter(char::is_ascii_uppercase).count() >= 5, "{}", passphrase);
+        }
+    }
+
+}
@@ -0,0 +1,363 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Named policies selected per user.
+//!
+//! A `PolicySet` holds several configured instances, each under a name, and selects the one
+//! applying to an account from its name, its role, or the groups it belongs to.
+//! Selectors are tried in this order: a selector on the username first, then on the role,
+//! then on the groups, and the default policy last. Among selectors of the same kind, the
+//! first one added wins. Policies must be added before the selectors naming them.
+
+use pwquality_sys::Error;
+
+use users;
+use PWQuality;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The account a password is checked for.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Account {
+    /// The name of the user.
+    pub name: String,
+    /// The names of the groups the user belongs to.
+    pub groups: Vec<String>,
+    /// The role of the user, given by the caller.
+    pub role: Option<String>,
+}
+
+impl Account {
+
+    /// Creates an account with the given username, without groups nor role.
+    pub fn new<S: Into<String>>(name: S) -> Self {
+        Account {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates the account of the system user with the given name, with the groups it
+    /// belongs to looked up in the system databases.
+    /// An error of kind `NotFound` is returned if the user does not exist.
+    pub fn from_system(name: &str) -> io::Result<Self> {
+        let passwd = users::getpwnam(name)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("unknown user {}", name)))?;
+        let groups = users::group_names(&passwd.name, passwd.gid)?;
+        Ok(Account {
+            name: passwd.name,
+            groups,
+            role: None,
+        })
+    }
+
+    /// Sets the role of the account.
+    pub fn with_role<S: Into<String>>(mut self, role: S) -> Self {
+        self.role = Some(role.into());
+        self
+    }
+
+}
+
+/// What a policy is selected by.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Selector {
+    /// The account has the given username.
+    User(String),
+    /// The account belongs to the given group.
+    Group(String),
+    /// The account has the given role.
+    Role(String),
+}
+
+impl Selector {
+
+    fn matches(&self, account: &Account) -> bool {
+        match *self {
+            Selector::User(ref name) => account.name == *name,
+            Selector::Group(ref group) => account.groups.contains(group),
+            Selector::Role(ref role) => account.role.as_ref() == Some(role),
+        }
+    }
+
+    fn priority(&self) -> usize {
+        match *self {
+            Selector::User(_) => 0,
+            Selector::Role(_) => 1,
+            Selector::Group(_) => 2,
+        }
+    }
+
+}
+
+/// The result of checking a password with the policy of an account.
+#[derive(Clone, Debug)]
+pub struct PolicyCheck {
+    policy: String,
+    result: Result<i32, Error>,
+}
+
+impl PolicyCheck {
+
+    /// Returns the name of the policy applied.
+    pub fn policy(&self) -> &str {
+        &self.policy
+    }
+
+    /// Returns the result of the check.
+    pub fn result(&self) -> &Result<i32, Error> {
+        &self.result
+    }
+
+    /// Returns whether the password passed the checks.
+    pub fn is_accepted(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Returns the score of the password, if it passed the checks.
+    pub fn score(&self) -> Option<i32> {
+        self.result.as_ref().ok().cloned()
+    }
+
+    /// Returns the reason why the password was rejected, if it did not pass the checks.
+    pub fn error(&self) -> Option<&Error> {
+        self.result.as_ref().err()
+    }
+
+}
+
+/// A set of named policies, and the rules to select them.
+#[derive(Debug, Default)]
+pub struct PolicySet {
+    policies: Vec<(String, PWQuality)>,
+    selectors: Vec<(Selector, String)>,
+    default: Option<String>,
+}
+
+impl PolicySet {
+
+    /// Creates an empty set of policies.
+    pub fn new() -> Self {
+        PolicySet::default()
+    }
+
+    /// Adds a policy with the given name, replacing the policy with the same name if any.
+    pub fn add_policy<S: Into<String>>(&mut self, name: S, policy: PWQuality) {
+        let name = name.into();
+        self.policies.retain(|(n, _)| *n != name);
+        self.policies.push((name, policy));
+    }
+
+    /// Adds a policy with the given name, configured with the given configuration file.
+    pub fn load_policy<S: Into<String>, P: AsRef<Path>>(&mut self, name: S, config_path: P) -> Result<(), Error> {
+        let policy = PWQuality::from_config(config_path)?;
+        self.add_policy(name, policy);
+        Ok(())
+    }
+
+    /// Adds a policy for each `.conf` file of the given directory, named after the file
+    /// without its extension (e.g. `admins.conf` defines the `admins` policy).
+    pub fn load_dir<P: AsRef<Path>>(&mut self, dir: P) -> Result<(), Error> {
+        let entries = fs::read_dir(dir).map_err(|_| Error::CfgfileOpen)?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().map(|ext| ext == "conf").unwrap_or(false))
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Some(name) = path.file_stem().and_then(|stem| stem.to_str()).map(str::to_owned) {
+                self.load_policy(name, &path)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the policy with the given name.
+    pub fn policy(&self, name: &str) -> Option<&PWQuality> {
+        self.policies.iter().find(|&(n, _)| n == name).map(|(_, policy)| policy)
+    }
+
+    /// Returns the names of the policies.
+    pub fn policy_names(&self) -> Vec<&str> {
+        self.policies.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Selects the policy with the given name for the accounts matching the selector.
+    /// An `UnknownPolicy` error is returned if there is no policy with this name.
+    pub fn assign<S: Into<String>>(&mut self, selector: Selector, policy: S) -> Result<(), Error> {
+        let policy = self.known(policy.into())?;
+        self.selectors.push((selector, policy));
+        Ok(())
+    }
+
+    /// Sets the name of the policy applied to accounts not matched by any selector.
+    /// A value of `None` leaves them without policy.
+    /// An `UnknownPolicy` error is returned if there is no policy with this name.
+    pub fn set_default(&mut self, policy: Option<String>) -> Result<(), Error> {
+        self.default = match policy {
+            Some(policy) => Some(self.known(policy)?),
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn known(&self, policy: String) -> Result<String, Error> {
+        match self.policy(&policy) {
+            Some(_) => Ok(policy),
+            None => Err(Error::UnknownPolicy { policy }),
+        }
+    }
+
+    /// Returns the name of the policy applied to accounts not matched by any selector.
+    pub fn get_default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+
+    /// Returns the name of the policy applying to the account, and the policy.
+    pub fn select(&self, account: &Account) -> Option<(&str, &PWQuality)> {
+        let mut matching: Vec<&(Selector, String)> = self.selectors.iter()
+            .filter(|&(selector, _)| selector.matches(account))
+            .collect();
+        // the sort is stable, so the first added selector of a kind wins
+        matching.sort_by_key(|&(selector, _)| selector.priority());
+        matching.into_iter()
+            .map(|(_, name)| name)
+            .chain(self.default.as_ref())
+            .filter_map(|name| self.policy(name).map(|policy| (name.as_str(), policy)))
+            .next()
+    }
+
+    /// Checks a password with the policy applying to the account, the name of the account
+    /// being given to the check as username.
+    /// A `NoPolicy` error is returned if no policy applies to the account.
+    pub fn check_for(&self, account: &Account, password: String) -> Result<PolicyCheck, Error> {
+        match self.select(account) {
+            Some((name, policy)) => Ok(PolicyCheck {
+                policy: name.to_owned(),
+                result: policy.check(password, None, Some(account.name.clone())),
+            }),
+            None => Err(Error::NoPolicy { user: account.name.clone() }),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    const PASSWORD: &str = "Wq-7-Zt!x-Vb4";
+
+    fn policy(min_length: i32) -> PWQuality {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(min_length);
+        pwq.set_digit_credit(0);
+        pwq.set_uppercase_credit(0);
+        pwq.set_lowercase_credit(0);
+        pwq.set_other_credit(0);
+        pwq
+    }
+
+    fn policies() -> PolicySet {
+        let mut set = PolicySet::new();
+        for &(name, min_length) in &[("default", 8), ("staff", 10), ("admins", 14), ("root", 20), ("dba", 16)] {
+            set.add_policy(name, policy(min_length));
+        }
+        set
+    }
+
+    fn selected<'a>(set: &'a PolicySet, account: &Account) -> Option<&'a str> {
+        set.select(account).map(|(name, _)| name)
+    }
+
+    #[test]
+    fn selector_priority() {
+        let mut set = policies();
+        set.set_default(Some("default".to_owned())).unwrap();
+        // added in reverse order of priority
+        set.assign(Selector::Group("wheel".to_owned()), "admins").unwrap();
+        set.assign(Selector::Role("operator".to_owned()), "staff").unwrap();
+        set.assign(Selector::User("alice".to_owned()), "root").unwrap();
+        let alice = Account {
+            name: "alice".to_owned(),
+            groups: vec!["wheel".to_owned()],
+            role: Some("operator".to_owned()),
+        };
+        assert_eq!(selected(&set, &alice), Some("root"));
+        let bob = Account {
+            name: "bob".to_owned(),
+            ..alice.clone()
+        };
+        assert_eq!(selected(&set, &bob), Some("staff"));
+        assert_eq!(selected(&set, &Account { role: None, ..bob.clone() }), Some("admins"));
+        assert_eq!(selected(&set, &Account::new("carol")), Some("default"));
+        assert_eq!(selected(&set, &Account::new("carol").with_role("operator")), Some("staff"));
+    }
+
+    #[test]
+    fn first_added_wins() {
+        let mut set = policies();
+        set.assign(Selector::Group("dba".to_owned()), "dba").unwrap();
+        set.assign(Selector::Group("wheel".to_owned()), "admins").unwrap();
+        set.assign(Selector::Group("dba".to_owned()), "staff").unwrap();
+        let account = Account {
+            groups: vec!["wheel".to_owned(), "dba".to_owned()],
+            ..Account::new("alice")
+        };
+        assert_eq!(selected(&set, &account), Some("dba"));
+        // replacing a policy keeps its selectors
+        set.add_policy("dba", policy(18));
+        assert_eq!(set.select(&account).map(|(_, pwq)| pwq.get_min_length()), Some(18));
+    }
+
+    #[test]
+    fn unknown_policies() {
+        let mut set = policies();
+        assert_eq!(set.assign(Selector::User("alice".to_owned()), "guests"),
+                   Err(Error::UnknownPolicy { policy: "guests".to_owned() }));
+        assert_eq!(set.set_default(Some("guests".to_owned())), Err(Error::UnknownPolicy { policy: "guests".to_owned() }));
+        assert_eq!(selected(&set, &Account::new("alice")), None);
+        set.set_default(Some("default".to_owned())).unwrap();
+        set.set_default(None).unwrap();
+        assert_eq!(set.get_default(), None);
+    }
+
+    #[test]
+    fn no_policy() {
+        let mut set = policies();
+        set.assign(Selector::User("alice".to_owned()), "admins").unwrap();
+        match set.check_for(&Account::new("bob"), PASSWORD.to_owned()) {
+            Err(Error::NoPolicy { ref user }) if user == "bob" => (),
+            result => panic!("unexpected result {:?}", result),
+        }
+        assert!(PolicySet::new().check_for(&Account::new("bob"), PASSWORD.to_owned()).is_err());
+        let check = set.check_for(&Account::new("alice"), PASSWORD.to_owned()).unwrap();
+        assert_eq!(check.policy(), "admins");
+        assert!(!check.is_accepted());
+        assert_eq!(check.error(), Some(&Error::MinLength));
+        set.set_default(Some("default".to_owned())).unwrap();
+        let check = set.check_for(&Account::new("bob"), PASSWORD.to_owned()).unwrap();
+        assert_eq!(check.policy(), "default");
+        assert_eq!(check.score(), Some(50));
+    }
+
+    #[test]
+    fn load_dir() {
+        let dir = env::temp_dir().join(format!("pwquality-policies-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in &["staff.conf", "admins.conf", "README", "old.conf.bak"] {
+            fs::write(dir.join(name), "minlen = 12\n").unwrap();
+        }
+        let mut set = PolicySet::new();
+        let result = set.load_dir(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        assert_eq!(set.policy_names(), ["admins", "staff"]);
+        assert_eq!(PolicySet::new().load_dir(dir.join("missing")), Err(Error::CfgfileOpen));
+    }
+
+}
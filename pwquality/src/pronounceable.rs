@@ -0,0 +1,353 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Generation of pronounceable passwords from a syllable model.
+//!
+//! A pronounceable password is a sequence of syllables, each made of an onset (one or more
+//! consonants) followed by a nucleus (one or more vowels), optionally ended by a coda
+//! (consonants). As onsets only contain consonants and nuclei only contain vowels, a password
+//! can be split back into the elements it was built from, so every sequence of choices gives
+//! a distinct password and the reported entropy is exact.
+//!
+//! Language packs are provided for English and French, other models can be built with
+//! `SyllableModel::new`.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use passphrase::{self, SYMBOLS};
+use requirements::Requirements;
+
+use std::collections::HashSet;
+
+const VOWELS: &str = "aeiou";
+
+const ENGLISH_ONSETS: &[&str] = &[
+    "b", "c", "d", "f", "g", "h", "j", "k", "l", "m", "n", "p", "r", "s", "t", "v", "w", "z",
+    "bl", "br", "ch", "cl", "cr", "dr", "fl", "fr", "gl", "gr", "pl", "pr", "sc", "sh", "sk",
+    "sl", "sm", "sn", "sp", "st", "sw", "th", "tr", "wh", "str", "spr", "thr",
+];
+const ENGLISH_NUCLEI: &[&str] = &["a", "e", "i", "o", "u", "ai", "ea", "ee", "ie", "oa", "oo", "ou"];
+const ENGLISH_CODAS: &[&str] = &[
+    "", "b", "ck", "d", "ft", "g", "k", "l", "ll", "m", "n", "nd", "ng", "nk", "nt", "p", "r",
+    "rd", "rk", "rn", "s", "sh", "sk", "ss", "st", "t", "th", "x",
+];
+
+const FRENCH_ONSETS: &[&str] = &[
+    "b", "c", "d", "f", "g", "j", "l", "m", "n", "p", "r", "s", "t", "v", "ch", "bl", "br",
+    "cl", "cr", "dr", "fl", "fr", "gl", "gn", "gr", "pl", "pr", "tr", "vr",
+];
+const FRENCH_NUCLEI: &[&str] = &["a", "e", "i", "o", "u", "ai", "au", "eau", "ei", "eu", "oi", "ou"];
+const FRENCH_CODAS: &[&str] = &["", "c", "l", "n", "r", "s", "t", "x", "nt", "rd", "rs", "rt"];
+
+/// A syllable model, giving the onsets, nuclei and codas syllables are built from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyllableModel {
+    name: String,
+    onsets: Vec<String>,
+    nuclei: Vec<String>,
+    codas: Vec<String>,
+}
+
+fn is_vowel(c: char) -> bool {
+    VOWELS.contains(c)
+}
+
+fn is_consonant(c: char) -> bool {
+    c.is_ascii_lowercase() && !is_vowel(c)
+}
+
+fn keep<'a, I: IntoIterator<Item = &'a str>>(elements: I, allow_empty: bool, valid: fn(char) -> bool) -> Vec<String> {
+    let mut seen = HashSet::new();
+    elements.into_iter()
+        .map(str::trim)
+        .filter(|e| (allow_empty || !e.is_empty()) && e.chars().all(valid) && seen.insert(e.to_owned()))
+        .map(str::to_owned)
+        .collect()
+}
+
+impl SyllableModel {
+
+    /// Creates a syllable model with the given elements.
+    /// Onsets and codas are made of lowercase ASCII consonants, nuclei of lowercase
+    /// ASCII vowels (`y` is a consonant). Elements not respecting this, as well as
+    /// duplicates, are ignored. The empty coda may be given to allow passwords ending
+    /// with a vowel.
+    pub fn new<S: Into<String>>(name: S, onsets: &[&str], nuclei: &[&str], codas: &[&str]) -> Self {
+        SyllableModel {
+            name: name.into(),
+            onsets: keep(onsets.iter().cloned(), false, is_consonant),
+            nuclei: keep(nuclei.iter().cloned(), false, is_vowel),
+            codas: keep(codas.iter().cloned(), true, is_consonant),
+        }
+    }
+
+    /// The English language pack.
+    pub fn english() -> Self {
+        SyllableModel::new("english", ENGLISH_ONSETS, ENGLISH_NUCLEI, ENGLISH_CODAS)
+    }
+
+    /// The French language pack.
+    pub fn french() -> Self {
+        SyllableModel::new("french", FRENCH_ONSETS, FRENCH_NUCLEI, FRENCH_CODAS)
+    }
+
+    /// Returns the language pack with the given name (`english` or `french`).
+    pub fn by_name(name: &str) -> Option<Self> {
+        match name {
+            "english" => Some(SyllableModel::english()),
+            "french" => Some(SyllableModel::french()),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of this model.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the onsets of this model.
+    pub fn onsets(&self) -> &[String] {
+        &self.onsets
+    }
+
+    /// Returns the nuclei of this model.
+    pub fn nuclei(&self) -> &[String] {
+        &self.nuclei
+    }
+
+    /// Returns the codas of this model.
+    pub fn codas(&self) -> &[String] {
+        &self.codas
+    }
+
+    /// Returns whether passwords can be generated with this model,
+    /// i.e. whether it has at least one onset and one nucleus.
+    pub fn is_usable(&self) -> bool {
+        !self.onsets.is_empty() && !self.nuclei.is_empty()
+    }
+
+    /// Returns the entropy in bits of a syllable generated with this model.
+    pub fn entropy_per_syllable(&self) -> f64 {
+        (self.onsets.len() as f64).log2() + (self.nuclei.len() as f64).log2()
+    }
+
+    // the length of the shortest syllable
+    fn min_syllable_length(&self) -> usize {
+        let shortest = |elements: &[String]| elements.iter().map(String::len).min().unwrap_or(0);
+        shortest(&self.onsets) + shortest(&self.nuclei)
+    }
+
+}
+
+impl Default for SyllableModel {
+    fn default() -> Self {
+        SyllableModel::english()
+    }
+}
+
+/// The options of pronounceable password generation.
+#[derive(Clone, Debug)]
+pub struct PronounceableOptions {
+    /// The model to build syllables from.
+    pub model: SyllableModel,
+    /// The minimum number of syllables in the password.
+    /// More syllables are generated if needed to reach the minimum length of the instance.
+    pub syllables: usize,
+    /// The minimum number of syllables starting with an uppercase letter.
+    pub uppercase: usize,
+    /// The minimum number of digits to add to the password.
+    pub digits: usize,
+    /// The minimum number of symbols to add to the password.
+    pub symbols: usize,
+    /// The maximum number of passwords generated before giving up when they do not
+    /// pass the checks.
+    pub max_attempts: usize,
+}
+
+impl Default for PronounceableOptions {
+    fn default() -> Self {
+        PronounceableOptions {
+            model: SyllableModel::english(),
+            syllables: 5,
+            uppercase: 0,
+            digits: 0,
+            symbols: 0,
+            max_attempts: 10,
+        }
+    }
+}
+
+/// A generated pronounceable password, together with the entropy of its generation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pronounceable {
+    password: String,
+    entropy: f64,
+}
+
+impl Pronounceable {
+
+    /// Returns the password.
+    pub fn as_str(&self) -> &str {
+        &self.password
+    }
+
+    /// Returns the password.
+    pub fn into_string(self) -> String {
+        self.password
+    }
+
+    /// Returns the entropy in bits of the generation, i.e. the base 2 logarithm of the number
+    /// of equally likely passwords that could have been generated with the same options.
+    pub fn entropy(&self) -> f64 {
+        self.entropy
+    }
+
+}
+
+fn pick<'a, R: Rng>(rng: &mut R, elements: &'a [String]) -> &'a str {
+    &elements[rng.gen_range(0..elements.len())]
+}
+
+/// Generates a pronounceable password with the given options, adding what is needed to meet the requirements.
+/// The model must be usable.
+pub(crate) fn generate<R: Rng>(rng: &mut R, options: &PronounceableOptions, requirements: &Requirements) -> Pronounceable {
+    let model = &options.model;
+    let mut uppercase = options.uppercase.max(requirements.uppercase);
+    let mut digits = options.digits.max(requirements.digits);
+    let mut symbols = options.symbols.max(requirements.others);
+    // add the missing classes, lowercase letters are always present in nuclei
+    let classes = |uppercase: usize, digits: usize, symbols: usize| {
+        1 + (uppercase > 0) as usize + (digits > 0) as usize + (symbols > 0) as usize
+    };
+    while classes(uppercase, digits, symbols) < requirements.min_classes.min(4) {
+        if uppercase == 0 {
+            uppercase = 1;
+        } else if digits == 0 {
+            digits = 1;
+        } else {
+            symbols = 1;
+        }
+    }
+    // the number of syllables only depends on the options, so that the entropy is exact
+    let missing = requirements.min_length.saturating_sub(digits + symbols);
+    let min_syllable_length = model.min_syllable_length().max(1);
+    let n = options.syllables.max(1).max(missing.div_ceil(min_syllable_length));
+    let uppercase = uppercase.min(n);
+
+    let mut syllables: Vec<String> = (0..n)
+        .map(|_| format!("{}{}", pick(rng, &model.onsets), pick(rng, &model.nuclei)))
+        .collect();
+    let mut entropy = n as f64 * model.entropy_per_syllable();
+    if !model.codas.is_empty() {
+        let coda = pick(rng, &model.codas);
+        syllables[n - 1].push_str(coda);
+        entropy += (model.codas.len() as f64).log2();
+    }
+    if uppercase > 0 {
+        let mut indices: Vec<usize> = (0..n).collect();
+        indices.shuffle(rng);
+        for &idx in &indices[..uppercase] {
+            let mut chars = syllables[idx].chars();
+            syllables[idx] = match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            };
+        }
+        entropy += passphrase::log2_binomial(n, uppercase);
+    }
+    // digits then symbols are appended to the syllable at the same random position,
+    // or put in front of the password
+    if digits > 0 || symbols > 0 {
+        let mut sprinkle = String::new();
+        for _ in 0..digits {
+            sprinkle.push(char::from(b'0' + rng.gen_range(0..10u8)));
+        }
+        let symbol_chars: Vec<char> = SYMBOLS.chars().collect();
+        for _ in 0..symbols {
+            sprinkle.push(symbol_chars[rng.gen_range(0..symbol_chars.len())]);
+        }
+        let position = rng.gen_range(0..=n);
+        if position == 0 {
+            syllables[0].insert_str(0, &sprinkle);
+        } else {
+            syllables[position - 1].push_str(&sprinkle);
+        }
+        entropy += ((n + 1) as f64).log2() + digits as f64 * 10f64.log2()
+            + symbols as f64 * (symbol_chars.len() as f64).log2();
+    }
+    Pronounceable {
+        password: syllables.concat(),
+        entropy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn models() {
+        let model = SyllableModel::new("test", &["b", "b", "ae", "Tr", "tr"], &["a", "e", "y", ""], &["", "s", "e"]);
+        assert_eq!(model.onsets(), &["b".to_owned(), "tr".to_owned()][..]);
+        assert_eq!(model.nuclei(), &["a".to_owned(), "e".to_owned()][..]);
+        assert_eq!(model.codas(), &["".to_owned(), "s".to_owned()][..]);
+        assert!(model.is_usable());
+        assert_eq!(model.entropy_per_syllable(), 2.0);
+        assert!(!SyllableModel::new("empty", &[], &["a"], &[]).is_usable());
+        assert_eq!(SyllableModel::by_name("french"), Some(SyllableModel::french()));
+        assert_eq!(SyllableModel::by_name("klingon"), None);
+    }
+
+    #[test]
+    fn entropy() {
+        let options = PronounceableOptions {
+            model: SyllableModel::new("test", &["b", "d"], &["a", "o"], &["", "s"]),
+            syllables: 4,
+            ..PronounceableOptions::default()
+        };
+        let password = generate(&mut StdRng::seed_from_u64(0), &options, &Requirements::default());
+        // 2 bits per syllable and 1 bit for the coda
+        assert_eq!(password.entropy(), 9.0);
+        assert!(password.as_str().len() == 8 || password.as_str().len() == 9);
+
+        let options = PronounceableOptions {
+            uppercase: 2,
+            digits: 1,
+            ..options
+        };
+        let password = generate(&mut StdRng::seed_from_u64(0), &options, &Requirements::default());
+        let expected = 9.0 + passphrase::log2_binomial(4, 2) + 5f64.log2() + 10f64.log2();
+        assert!((password.entropy() - expected).abs() < 1e-9);
+        assert_eq!(password.as_str().chars().filter(char::is_ascii_uppercase).count(), 2);
+        assert_eq!(password.as_str().chars().filter(char::is_ascii_digit).count(), 1);
+    }
+
+    #[test]
+    fn requirements() {
+        let requirements = Requirements {
+            min_length: 20,
+            others: 2,
+            min_classes: 4,
+            ..Requirements::default()
+        };
+        let options = PronounceableOptions {
+            syllables: 1,
+            ..PronounceableOptions::default()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..20 {
+            let password = generate(&mut rng, &options, &requirements);
+            let password = password.as_str();
+            assert!(password.len() >= 20, "{}", password);
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()), "{}", password);
+            assert!(password.chars().any(|c| c.is_ascii_lowercase()), "{}", password);
+            assert!(password.chars().any(|c| c.is_ascii_digit()), "{}", password);
+            assert!(password.chars().filter(|&c| SYMBOLS.contains(c)).count() >= 2, "{}", password);
+        }
+    }
+
+}
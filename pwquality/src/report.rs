@@ -0,0 +1,317 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Detailed result of a password check.
+//!
+//! With the `serde` feature, `CheckReport` and `FullCheck` can be serialized. A report is
+//! serialized as an object with the following fields, described by the JSON schema
+//! `CHECK_REPORT_SCHEMA` (`schema/check-report.schema.json`):
+//!
+//!  - `accepted`: whether the password passed the checks,
+//!  - `score`: the score of the password, `null` if it was rejected,
+//!  - `error`: the reason why the password was rejected, `null` if it was accepted,
+//!  - `guesses`: the estimated number of guesses needed to find the password,
+//!  - `suggestions`: the changes that would make the password meet all the requirements.
+//!
+//! The patterns of the estimate are not serialized.
+
+use estimate::Estimate;
+use suggestion::Suggestion;
+
+use pwquality_sys::Error;
+
+/// The JSON schema of a serialized `CheckReport`, and of the errors and suggestions it contains.
+#[cfg(feature = "serde")]
+pub const CHECK_REPORT_SCHEMA: &str = include_str!("../schema/check-report.schema.json");
+
+/// The detailed result of checking a password, returned by `PWQuality::check_report`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "ReportPayload", from = "ReportPayload"))]
+pub struct CheckReport {
+    score: Option<i32>,
+    error: Option<Error>,
+    estimate: Estimate,
+    suggestions: Vec<Suggestion>,
+}
+
+impl CheckReport {
+
+    pub(crate) fn new(result: Result<i32, Error>, estimate: Estimate, suggestions: Vec<Suggestion>) -> Self {
+        let (score, error) = match result {
+            Ok(score) => (Some(score), None),
+            Err(error) => (None, Some(error)),
+        };
+        CheckReport {
+            score,
+            error,
+            estimate,
+            suggestions,
+        }
+    }
+
+    /// Returns whether the password passed the checks.
+    pub fn is_accepted(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Returns the score of the password, if it passed the checks.
+    pub fn score(&self) -> Option<i32> {
+        self.score
+    }
+
+    /// Returns the reason why the password was rejected, if it did not pass the checks.
+    pub fn error(&self) -> Option<&Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the estimation of the number of guesses needed to find the password.
+    pub fn estimate(&self) -> &Estimate {
+        &self.estimate
+    }
+
+    /// Returns the changes that would make the password meet all the requirements of the
+    /// settings, not only the one it was rejected for.
+    pub fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
+
+}
+
+// the serialized form of a report
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct ReportPayload {
+    accepted: bool,
+    score: Option<i32>,
+    error: Option<Error>,
+    guesses: f64,
+    suggestions: Vec<Suggestion>,
+}
+
+#[cfg(feature = "serde")]
+impl From<CheckReport> for ReportPayload {
+    fn from(report: CheckReport) -> Self {
+        ReportPayload {
+            accepted: report.is_accepted(),
+            score: report.score,
+            error: report.error,
+            guesses: report.estimate.guesses(),
+            suggestions: report.suggestions,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<ReportPayload> for CheckReport {
+    fn from(payload: ReportPayload) -> Self {
+        let result = match payload.error {
+            Some(error) => Err(error),
+            None => Ok(payload.score.unwrap_or(0)),
+        };
+        CheckReport::new(result, Estimate::from_guesses(payload.guesses), payload.suggestions)
+    }
+}
+
+/// All the violations of the settings found in a password, returned by `PWQuality::check_all`.
+///
+/// The length and class checks are run by this crate, the other checks of the library are run
+/// again with each failed check disabled, until the password passes. The checks of the library
+/// that cannot be disabled (e.g. the palindrome check) hide the checks of the library run after
+/// them, and so does a password shorter than `6` characters, the lowest minimum length of the
+/// library, but not the additional checks of this crate.
+///
+/// Each violation has a penalty: the number of characters to add, or to remove from runs,
+/// sequences and keyboard walks, to fix it, at least `1` for the checks that are not counted
+/// in characters (e.g. a dictionary word).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FullCheck {
+    violations: Vec<Error>,
+    penalties: Vec<usize>,
+    score: i32,
+}
+
+impl FullCheck {
+
+    pub(crate) fn new(violations: Vec<Error>, penalties: Vec<usize>, score: i32) -> Self {
+        FullCheck {
+            violations,
+            penalties,
+            score,
+        }
+    }
+
+    /// Returns whether the password passed all the checks.
+    pub fn is_accepted(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Returns the violations found, in the order the checks are run.
+    pub fn violations(&self) -> &[Error] {
+        &self.violations
+    }
+
+    /// Returns the penalties of the violations, in the same order.
+    pub fn penalties(&self) -> &[usize] {
+        &self.penalties
+    }
+
+    /// Returns the score the password would get if the violated checks were disabled,
+    /// `0` if the library checks could not all be run.
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use PWQuality;
+
+    use pwquality_sys::Error;
+
+    fn pwq() -> PWQuality {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(12);
+        pwq.set_digit_credit(-1);
+        pwq.set_uppercase_credit(-1);
+        pwq.set_lowercase_credit(0);
+        pwq.set_other_credit(0);
+        pwq.set_min_classes(3);
+        pwq.set_user_check(true);
+        pwq
+    }
+
+    #[test]
+    fn all_violations() {
+        let check = pwq().check_all("johnabc".to_owned(), None, Some("john".to_owned()));
+        assert!(!check.is_accepted());
+        assert_eq!(check.violations(), &[Error::MinDigits, Error::MinUppers, Error::MinLength, Error::MinClasses,
+                                        Error::UserCheck]);
+        assert_eq!(check.penalties(), &[1, 1, 3, 1, 1]);
+    }
+
+    #[test]
+    fn too_short_for_the_library() {
+        let check = pwq().check_all("ab1".to_owned(), None, None);
+        assert_eq!(check.violations(), &[Error::MinUppers, Error::MinLength, Error::MinClasses]);
+        assert_eq!(check.penalties(), &[1, 8, 1]);
+        assert_eq!(check.score(), 0);
+    }
+
+    #[test]
+    fn accepted() {
+        let pwq = pwq();
+        let password = "Abcdef-12345-xyz";
+        let check = pwq.check_all(password.to_owned(), None, Some("john".to_owned()));
+        assert!(check.is_accepted());
+        assert!(check.penalties().is_empty());
+        assert_eq!(Ok(check.score()), pwq.check(password.to_owned(), None, Some("john".to_owned())));
+    }
+
+    #[cfg(feature = "serde")]
+    mod serialization {
+        use super::pwq;
+        use report::{CheckReport, FullCheck, CHECK_REPORT_SCHEMA};
+        use suggestion::Suggestion;
+
+        use pwquality_sys::Error;
+
+        use jsonschema::JSONSchema;
+        use serde_json::{self, Value};
+
+        // validates the value against the schema, or against one of its definitions
+        fn validate(value: &Value, definition: Option<&str>) {
+            let mut schema: Value = serde_json::from_str(CHECK_REPORT_SCHEMA).unwrap();
+            if let Some(definition) = definition {
+                schema = json!({
+                    "$schema": schema["$schema"],
+                    "$defs": schema["$defs"],
+                    "$ref": format!("#/$defs/{}", definition),
+                });
+            }
+            let compiled = JSONSchema::compile(&schema).unwrap();
+            let errors: Vec<String> = match compiled.validate(value) {
+                Ok(()) => return,
+                Err(errors) => errors.map(|error| error.to_string()).collect(),
+            };
+            panic!("{} does not match the schema: {:?}", value, errors);
+        }
+
+        #[test]
+        fn errors_round_trip() {
+            for error in Error::examples() {
+                let value = serde_json::to_value(&error).unwrap();
+                assert_eq!(value["code"], error.name());
+                validate(&value, Some("error"));
+                assert_eq!(serde_json::from_value::<Error>(value).unwrap(), error);
+            }
+            assert_eq!(serde_json::to_value(Error::MinDigits).unwrap(), json!({ "code": "min_digits" }));
+            assert_eq!(serde_json::to_value(Error::BadWords { word: None }).unwrap(),
+                       json!({ "code": "bad_words", "details": { "word": null } }));
+        }
+
+        #[test]
+        fn suggestions_round_trip() {
+            let suggestions = vec![
+                Suggestion::AddCharacters(2), Suggestion::AddDigits(1), Suggestion::AddUppercase(1),
+                Suggestion::AddLowercase(1), Suggestion::AddOthers(1), Suggestion::AddClasses(2),
+                Suggestion::AvoidRepeats(2), Suggestion::AvoidClassRepeats(3), Suggestion::AvoidSequences(3),
+                Suggestion::AvoidWord("john".to_owned()), Suggestion::AvoidKeyboardWalk("qwerty".to_owned()),
+                Suggestion::AvoidPalindrome, Suggestion::AvoidDictionaryWord, Suggestion::AvoidBreachedPassword,
+                Suggestion::DifferFromOld, Suggestion::Custom("Custom rule".to_owned()),
+            ];
+            for suggestion in suggestions {
+                let value = serde_json::to_value(&suggestion).unwrap();
+                assert_eq!(value["kind"], suggestion.kind());
+                validate(&value, Some("suggestion"));
+                assert_eq!(serde_json::from_value::<Suggestion>(value).unwrap(), suggestion);
+            }
+        }
+
+        #[test]
+        fn reports_round_trip() {
+            let pwq = pwq();
+            for &(password, accepted) in &[("johnabc", false), ("Abcdef-12345-xyz", true)] {
+                let report = pwq.check_report(password.to_owned(), None, Some("john".to_owned()));
+                assert_eq!(report.is_accepted(), accepted);
+                let value = serde_json::to_value(&report).unwrap();
+                validate(&value, None);
+                assert_eq!(value["accepted"], accepted);
+                let parsed: CheckReport = serde_json::from_value(value.clone()).unwrap();
+                assert_eq!(parsed.error(), report.error());
+                assert_eq!(parsed.score(), report.score());
+                assert_eq!(parsed.suggestions(), report.suggestions());
+                assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+            }
+        }
+
+        #[test]
+        fn full_checks_round_trip() {
+            let pwq = pwq();
+            for password in &["johnabc", "ab1", "Abcdef-12345-xyz"] {
+                let check = pwq.check_all(password.to_string(), None, Some("john".to_owned()));
+                let value = serde_json::to_value(&check).unwrap();
+                validate(&value, Some("full_check"));
+                let parsed: FullCheck = serde_json::from_value(value).unwrap();
+                assert_eq!(parsed.violations(), check.violations());
+                assert_eq!(parsed.penalties(), check.penalties());
+                assert_eq!(parsed.score(), check.score());
+            }
+        }
+
+        #[test]
+        fn invalid_values() {
+            validate(&json!({ "code": "min_digits" }), Some("error"));
+            let schema: Value = serde_json::from_str(CHECK_REPORT_SCHEMA).unwrap();
+            let error_schema = json!({ "$schema": schema["$schema"], "$defs": schema["$defs"], "$ref": "#/$defs/error" });
+            let error_schema = JSONSchema::compile(&error_schema).unwrap();
+            assert!(!error_schema.is_valid(&json!({ "code": "bad_word", "details": { "word": "acme" } })));
+            assert!(!error_schema.is_valid(&json!({ "code": "breached" })));
+            assert!(serde_json::from_value::<Error>(json!({ "code": "breached" })).is_err());
+        }
+
+    }
+
+}
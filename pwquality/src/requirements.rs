@@ -0,0 +1,40 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Constraints on generated passwords derived from the settings of an instance.
+
+use PWQuality;
+
+/// The minimum content a generated password must have to satisfy the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Requirements {
+    pub min_length: usize,
+    pub lowercase: usize,
+    pub uppercase: usize,
+    pub digits: usize,
+    pub others: usize,
+    pub min_classes: usize,
+    // the maximum credit a password can get for its classes of characters
+    pub max_credit: usize,
+}
+
+impl Requirements {
+
+    /// Derives the requirements from the minimum length, the negative credits and the minimum
+    /// number of classes of the instance.
+    pub fn from_settings(pwq: &PWQuality) -> Self {
+        let required = |credit: i32| if credit < 0 { (-credit) as usize } else { 0 };
+        let credit = |credit: i32| credit.max(0) as usize;
+        Requirements {
+            min_length: pwq.get_min_length().max(0) as usize,
+            lowercase: required(pwq.get_lowercase_credit()),
+            uppercase: required(pwq.get_uppercase_credit()),
+            digits: required(pwq.get_digit_credit()),
+            others: required(pwq.get_other_credit()),
+            min_classes: pwq.get_min_classes().max(0) as usize,
+            max_credit: credit(pwq.get_lowercase_credit()) + credit(pwq.get_uppercase_credit())
+                + credit(pwq.get_digit_credit()) + credit(pwq.get_other_credit()),
+        }
+    }
+
+}
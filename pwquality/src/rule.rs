@@ -0,0 +1,215 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Custom rules run after the checks of the library.
+//!
+//! A rule is registered on a `PWQuality` instance with `PWQuality::add_rule`,
+//! and is given the password being checked together with the score computed so far.
+//! It can either accept the password, adjust its score or reject it.
+
+use context::UserContext;
+
+use std::fmt;
+
+/// The data available to a rule when checking a password.
+#[derive(Clone, Copy, Debug)]
+pub struct CheckContext<'a> {
+    password: &'a str,
+    old_password: Option<&'a str>,
+    username: Option<&'a str>,
+    user: Option<&'a UserContext>,
+    score: i32,
+}
+
+impl<'a> CheckContext<'a> {
+
+    pub(crate) fn new(password: &'a str, old_password: Option<&'a str>, username: Option<&'a str>, user: Option<&'a UserContext>) -> Self {
+        CheckContext {
+            password,
+            old_password,
+            username,
+            user,
+            score: 0
+        }
+    }
+
+    pub(crate) fn with_score(self, score: i32) -> Self {
+        CheckContext {
+            score,
+            ..self
+        }
+    }
+
+    /// Returns the password being checked.
+    pub fn password(&self) -> &'a str {
+        self.password
+    }
+
+    /// Returns the old password, if any was given.
+    pub fn old_password(&self) -> Option<&'a str> {
+        self.old_password
+    }
+
+    /// Returns the username, if any was given.
+    pub fn username(&self) -> Option<&'a str> {
+        self.username
+    }
+
+    /// Returns the attributes of the user, if the password is checked with a context.
+    pub fn user_context(&self) -> Option<&'a UserContext> {
+        self.user
+    }
+
+    /// Returns the score computed so far, by the library and the previous rules.
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+}
+
+/// The result of a rule applied to a password.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RuleOutcome {
+    /// The password is accepted by the rule, its score is left unchanged.
+    Pass,
+    /// The password is accepted by the rule, and the given amount is added to its score.
+    /// The final score is always kept between `0` and `100`.
+    Adjust(i32),
+    /// The password is rejected, with the given error code and message.
+    Reject { code: i32, message: String },
+}
+
+/// A custom rule checking a password.
+pub trait Rule {
+
+    /// Checks the password in the given context.
+    fn check(&self, ctx: &CheckContext) -> RuleOutcome;
+
+}
+
+impl<F> Rule for F where F: Fn(&CheckContext) -> RuleOutcome {
+    fn check(&self, ctx: &CheckContext) -> RuleOutcome {
+        self(ctx)
+    }
+}
+
+/// The ordered list of rules registered on an instance.
+#[derive(Default)]
+pub(crate) struct Rules(pub(crate) Vec<Box<dyn Rule>>);
+
+impl fmt::Debug for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Rules({})", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use PWQuality;
+
+    use pwquality_sys::Error;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    // scores `abcdefghij` 40
+    fn pwq() -> PWQuality {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(6);
+        pwq.set_digit_credit(0);
+        pwq.set_uppercase_credit(0);
+        pwq.set_lowercase_credit(0);
+        pwq.set_other_credit(0);
+        pwq.set_min_classes(0);
+        pwq
+    }
+
+    fn check(pwq: &PWQuality, password: &str) -> Result<i32, Error> {
+        pwq.check(password.to_owned(), None, None)
+    }
+
+    fn no_z(ctx: &CheckContext) -> RuleOutcome {
+        if ctx.password().contains('z') {
+            RuleOutcome::Reject { code: -150, message: "z is forbidden".to_owned() }
+        } else {
+            RuleOutcome::Pass
+        }
+    }
+
+    #[test]
+    fn adjust_is_clamped() {
+        let pwq = pwq();
+        assert_eq!(check(&pwq, "abcdefghij"), Ok(40));
+        pwq.add_rule(|_: &CheckContext| RuleOutcome::Adjust(100));
+        assert_eq!(check(&pwq, "abcdefghij"), Ok(100));
+        pwq.clear_rules();
+        pwq.add_rule(|_: &CheckContext| RuleOutcome::Adjust(-1000));
+        assert_eq!(check(&pwq, "abcdefghij"), Ok(0));
+        pwq.clear_rules();
+        pwq.add_rule(|_: &CheckContext| RuleOutcome::Adjust(i32::MAX));
+        pwq.add_rule(|_: &CheckContext| RuleOutcome::Adjust(-10));
+        assert_eq!(check(&pwq, "abcdefghij"), Ok(90));
+    }
+
+    #[test]
+    fn reject() {
+        let pwq = pwq();
+        pwq.add_rule(no_z);
+        assert_eq!(check(&pwq, "abcdefghij"), Ok(40));
+        assert_eq!(check(&pwq, "abcdefghiz"), Err(Error::Custom { code: -150, message: "z is forbidden".to_owned() }));
+        let all = pwq.check_all("abcdefghiz".to_owned(), None, None);
+        assert_eq!(all.violations(), &[Error::Custom { code: -150, message: "z is forbidden".to_owned() }]);
+    }
+
+    #[test]
+    fn rules_run_in_order_after_the_library() {
+        let pwq = pwq();
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        for &(name, delta) in &[("first", 10), ("second", -5), ("third", 0)] {
+            let seen = seen.clone();
+            pwq.add_rule(move |ctx: &CheckContext| {
+                seen.borrow_mut().push((name, ctx.score()));
+                RuleOutcome::Adjust(delta)
+            });
+        }
+        assert_eq!(check(&pwq, "abcdefghij"), Ok(45));
+        assert_eq!(*seen.borrow(), [("first", 40), ("second", 50), ("third", 45)]);
+        // rules are not run on passwords rejected by the library
+        seen.borrow_mut().clear();
+        assert_eq!(check(&pwq, "abc"), Err(Error::MinLength));
+        assert!(seen.borrow().is_empty());
+    }
+
+    #[test]
+    fn a_later_rule_does_not_run_after_a_rejection() {
+        let pwq = pwq();
+        let ran = Rc::new(RefCell::new(false));
+        pwq.add_rule(no_z);
+        let flag = ran.clone();
+        pwq.add_rule(move |_: &CheckContext| {
+            *flag.borrow_mut() = true;
+            RuleOutcome::Pass
+        });
+        assert!(check(&pwq, "abcdefghiz").is_err());
+        assert!(!*ran.borrow());
+    }
+
+    #[test]
+    fn functions_are_rules() {
+        let user = UserContext::new("john");
+        let ctx = CheckContext::new("abcz", Some("old"), Some("john"), Some(&user)).with_score(12);
+        assert_eq!(ctx.score(), 12);
+        assert_eq!(ctx.old_password(), Some("old"));
+        assert_eq!(ctx.username(), Some("john"));
+        assert!(ctx.user_context().is_some());
+        assert_eq!(Rule::check(&no_z, &ctx), RuleOutcome::Reject { code: -150, message: "z is forbidden".to_owned() });
+        let bonus = 5;
+        let closure = move |ctx: &CheckContext| RuleOutcome::Adjust(ctx.score() + bonus);
+        assert_eq!(closure.check(&ctx), RuleOutcome::Adjust(17));
+        let boxed: Box<dyn Rule> = Box::new(closure);
+        assert_eq!(boxed.check(&ctx), RuleOutcome::Adjust(17));
+    }
+
+}
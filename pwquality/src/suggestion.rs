@@ -0,0 +1,579 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Suggestions to improve a rejected password.
+//!
+//! The library stops at the first failed check, so a rejected password only yields one error.
+//! The suggestions are computed from the settings of the instance and list all the unmet
+//! requirements at once. Requirements that can only be checked by the library (dictionary
+//! check, similarity with the old password, …) are only suggested when they are the reason
+//! of the rejection.
+
+use context::contains_word;
+use rule::CheckContext;
+#[cfg(feature = "unicode")]
+use unicode;
+use PWQuality;
+
+use pwquality_sys::{Error, Language};
+
+use std::fmt;
+
+/// A change that would make a password meet a requirement of the settings.
+///
+/// With the `serde` feature, a suggestion is serialized as an object with the snake case name
+/// of the variant as `kind` (e.g. `add_digits`), and its parameter, if any, as `value`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", content = "value", rename_all = "snake_case"))]
+pub enum Suggestion {
+    /// Add the given number of characters.
+    AddCharacters(usize),
+    /// Add the given number of digits.
+    AddDigits(usize),
+    /// Add the given number of uppercase letters.
+    AddUppercase(usize),
+    /// Add the given number of lowercase letters.
+    AddLowercase(usize),
+    /// Add the given number of non-alphanumeric characters.
+    AddOthers(usize),
+    /// Use characters of the given number of additional classes.
+    AddClasses(usize),
+    /// Do not repeat the same character more than the given number of times in a row.
+    AvoidRepeats(usize),
+    /// Do not use more than the given number of characters of the same class in a row.
+    AvoidClassRepeats(usize),
+    /// Do not use monotonic sequences (e.g. `abcd`, `4321`) longer than the given length.
+    AvoidSequences(usize),
+    /// Do not use the given word, forward or reversed (username, real name, forbidden word, …).
+    AvoidWord(String),
+    /// Do not use the given walk on adjacent keyboard keys.
+    AvoidKeyboardWalk(String),
+    /// Do not use a palindrome.
+    AvoidPalindrome,
+    /// Do not use a dictionary word.
+    AvoidDictionaryWord,
+    /// Do not use a password found in a breached passwords list.
+    AvoidBreachedPassword,
+    /// Change more characters of the old password.
+    DifferFromOld,
+    /// Fix the issue reported by a custom rule, with the given message.
+    Custom(String),
+}
+
+// the suggestions in English, French, German and Spanish, by kind of suggestion, `{}` being
+// replaced by the value of the suggestion
+static SUGGESTIONS: &[(&str, [&str; 4])] = &[
+    ("add_characters", [
+        "add {} more characters",
+        "ajoutez {} caractères de plus",
+        "fügen Sie {} weitere Zeichen hinzu",
+        "añada {} caracteres más"]),
+    ("add_digits", [
+        "add {} digits",
+        "ajoutez {} chiffres",
+        "fügen Sie {} Ziffern hinzu",
+        "añada {} dígitos"]),
+    ("add_uppercase", [
+        "add {} uppercase letters",
+        "ajoutez {} majuscules",
+        "fügen Sie {} Großbuchstaben hinzu",
+        "añada {} letras mayúsculas"]),
+    ("add_lowercase", [
+        "add {} lowercase letters",
+        "ajoutez {} minuscules",
+        "fügen Sie {} Kleinbuchstaben hinzu",
+        "añada {} letras minúsculas"]),
+    ("add_others", [
+        "add {} symbols",
+        "ajoutez {} symboles",
+        "fügen Sie {} Sonderzeichen hinzu",
+        "añada {} símbolos"]),
+    ("add_classes", [
+        "use {} more kinds of characters (digits, uppercase, lowercase, symbols)",
+        "utilisez {} types de caractères de plus (chiffres, majuscules, minuscules, symboles)",
+        "verwenden Sie {} weitere Zeichenarten (Ziffern, Groß-, Kleinbuchstaben, Sonderzeichen)",
+        "use {} tipos de caracteres más (dígitos, mayúsculas, minúsculas, símbolos)"]),
+    ("avoid_repeats", [
+        "avoid repeating the same character more than {} times in a row",
+        "évitez de répéter le même caractère plus de {} fois de suite",
+        "vermeiden Sie, dasselbe Zeichen mehr als {} Mal hintereinander zu wiederholen",
+        "evite repetir el mismo carácter más de {} veces seguidas"]),
+    ("avoid_class_repeats", [
+        "avoid more than {} characters of the same kind in a row",
+        "évitez plus de {} caractères du même type à la suite",
+        "vermeiden Sie mehr als {} Zeichen derselben Art hintereinander",
+        "evite más de {} caracteres del mismo tipo seguidos"]),
+    ("avoid_sequences", [
+        "avoid sequences such as 'abcd' or '4321' longer than {} characters",
+        "évitez les suites telles que 'abcd' ou '4321' de plus de {} caractères",
+        "vermeiden Sie Folgen wie 'abcd' oder '4321' mit mehr als {} Zeichen",
+        "evite secuencias como 'abcd' o '4321' de más de {} caracteres"]),
+    ("avoid_word", [
+        "avoid '{}'",
+        "évitez '{}'",
+        "vermeiden Sie '{}'",
+        "evite '{}'"]),
+    ("avoid_keyboard_walk", [
+        "avoid keyboard patterns such as '{}'",
+        "évitez les suites de touches du clavier telles que '{}'",
+        "vermeiden Sie Tastaturmuster wie '{}'",
+        "evite patrones del teclado como '{}'"]),
+    ("avoid_palindrome", [
+        "avoid palindromes",
+        "évitez les palindromes",
+        "vermeiden Sie Palindrome",
+        "evite los palíndromos"]),
+    ("avoid_dictionary_word", [
+        "avoid dictionary words",
+        "évitez les mots du dictionnaire",
+        "vermeiden Sie Wörter aus dem Wörterbuch",
+        "evite las palabras del diccionario"]),
+    ("avoid_breached_password", [
+        "avoid passwords that appeared in data breaches",
+        "évitez les mots de passe apparus dans des fuites de données",
+        "vermeiden Sie Passwörter, die in Datenlecks aufgetaucht sind",
+        "evite las contraseñas que aparecieron en filtraciones de datos"]),
+    ("differ_from_old", [
+        "change more characters of the old password",
+        "changez plus de caractères de l'ancien mot de passe",
+        "ändern Sie mehr Zeichen des alten Passworts",
+        "cambie más caracteres de la contraseña anterior"]),
+];
+
+// the suggestions adding a single character or class
+static SUGGESTIONS_ONE: &[(&str, [&str; 4])] = &[
+    ("add_characters", [
+        "add 1 more character",
+        "ajoutez 1 caractère de plus",
+        "fügen Sie 1 weiteres Zeichen hinzu",
+        "añada 1 carácter más"]),
+    ("add_digits", [
+        "add a digit",
+        "ajoutez un chiffre",
+        "fügen Sie eine Ziffer hinzu",
+        "añada un dígito"]),
+    ("add_uppercase", [
+        "add an uppercase letter",
+        "ajoutez une majuscule",
+        "fügen Sie einen Großbuchstaben hinzu",
+        "añada una letra mayúscula"]),
+    ("add_lowercase", [
+        "add a lowercase letter",
+        "ajoutez une minuscule",
+        "fügen Sie einen Kleinbuchstaben hinzu",
+        "añada una letra minúscula"]),
+    ("add_others", [
+        "add a symbol",
+        "ajoutez un symbole",
+        "fügen Sie ein Sonderzeichen hinzu",
+        "añada un símbolo"]),
+    ("add_classes", [
+        "use 1 more kind of character (digits, uppercase, lowercase, symbols)",
+        "utilisez 1 type de caractères de plus (chiffres, majuscules, minuscules, symboles)",
+        "verwenden Sie 1 weitere Zeichenart (Ziffern, Groß-, Kleinbuchstaben, Sonderzeichen)",
+        "use 1 tipo de caracteres más (dígitos, mayúsculas, minúsculas, símbolos)"]),
+];
+
+impl Suggestion {
+
+    /// Returns the snake case name of the variant, as serialized in `kind` with the `serde`
+    /// feature.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Suggestion::AddCharacters(_) => "add_characters",
+            Suggestion::AddDigits(_) => "add_digits",
+            Suggestion::AddUppercase(_) => "add_uppercase",
+            Suggestion::AddLowercase(_) => "add_lowercase",
+            Suggestion::AddOthers(_) => "add_others",
+            Suggestion::AddClasses(_) => "add_classes",
+            Suggestion::AvoidRepeats(_) => "avoid_repeats",
+            Suggestion::AvoidClassRepeats(_) => "avoid_class_repeats",
+            Suggestion::AvoidSequences(_) => "avoid_sequences",
+            Suggestion::AvoidWord(_) => "avoid_word",
+            Suggestion::AvoidKeyboardWalk(_) => "avoid_keyboard_walk",
+            Suggestion::AvoidPalindrome => "avoid_palindrome",
+            Suggestion::AvoidDictionaryWord => "avoid_dictionary_word",
+            Suggestion::AvoidBreachedPassword => "avoid_breached_password",
+            Suggestion::DifferFromOld => "differ_from_old",
+            Suggestion::Custom(_) => "custom",
+        }
+    }
+
+    /// Returns the suggestion in the language of the given locale (see
+    /// `pwquality_sys::Language::from_locale`). The message of a `Custom` suggestion is
+    /// returned as is.
+    pub fn message(&self, locale: &str) -> String {
+        let language = Language::from_locale(locale);
+        let kind = self.kind();
+        let text = |table| language.lookup(table, kind).unwrap_or(kind);
+        let value = match *self {
+            Suggestion::AddCharacters(n) | Suggestion::AddDigits(n) | Suggestion::AddUppercase(n)
+                | Suggestion::AddLowercase(n) | Suggestion::AddOthers(n)
+                | Suggestion::AddClasses(n) if n == 1 => return text(SUGGESTIONS_ONE).to_owned(),
+            Suggestion::AddCharacters(n) | Suggestion::AddDigits(n) | Suggestion::AddUppercase(n)
+                | Suggestion::AddLowercase(n) | Suggestion::AddOthers(n) | Suggestion::AddClasses(n)
+                | Suggestion::AvoidRepeats(n) | Suggestion::AvoidClassRepeats(n)
+                | Suggestion::AvoidSequences(n) => n.to_string(),
+            Suggestion::AvoidWord(ref word) | Suggestion::AvoidKeyboardWalk(ref word) => word.clone(),
+            Suggestion::Custom(ref message) => return message.clone(),
+            _ => return text(SUGGESTIONS).to_owned(),
+        };
+        text(SUGGESTIONS).replace("{}", &value)
+    }
+
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message("en"))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Digit,
+    Upper,
+    Lower,
+    Other,
+}
+
+// the class of a character as counted by the library, i.e. of ASCII characters only
+fn ascii_class(c: char) -> Class {
+    if c.is_ascii_digit() {
+        Class::Digit
+    } else if c.is_ascii_uppercase() {
+        Class::Upper
+    } else if c.is_ascii_lowercase() {
+        Class::Lower
+    } else {
+        Class::Other
+    }
+}
+
+// the classification of the characters by the checks of the instance
+#[cfg_attr(not(feature = "unicode"), allow(unused_variables))]
+fn classifier(pwq: &PWQuality) -> fn(char) -> Class {
+    #[cfg(feature = "unicode")]
+    {
+        if pwq.get_unicode_options().is_some_and(|options| options.script_classes) {
+            return |c| match unicode::classify(c) {
+                unicode::CharClass::Digit => Class::Digit,
+                unicode::CharClass::Uppercase => Class::Upper,
+                unicode::CharClass::Lowercase => Class::Lower,
+                unicode::CharClass::Other => Class::Other,
+            };
+        }
+    }
+    ascii_class
+}
+
+// the lengths of the runs of consecutive elements related by `same`
+fn runs<T, F: Fn(&T, &T) -> bool>(items: &[T], same: F) -> Vec<usize> {
+    let mut runs = Vec::new();
+    let mut current = items.len().min(1);
+    for pair in items.windows(2) {
+        if same(&pair[0], &pair[1]) {
+            current += 1;
+        } else {
+            runs.push(current);
+            current = 1;
+        }
+    }
+    runs.extend(Some(current).filter(|&current| current > 0));
+    runs
+}
+
+// the length of the longest run of consecutive elements related by `same`
+fn longest_run<T, F: Fn(&T, &T) -> bool>(items: &[T], same: F) -> usize {
+    runs(items, same).into_iter().max().unwrap_or(0)
+}
+
+// the lengths of the sequences of characters increasing or decreasing by one, two sequences
+// in opposite directions sharing their turning character
+fn sequences(chars: &[char]) -> Vec<usize> {
+    let steps: Vec<i64> = chars.windows(2).map(|pair| pair[1] as i64 - pair[0] as i64).collect();
+    let mut sequences = Vec::new();
+    let mut current = chars.len().min(1);
+    for (i, &step) in steps.iter().enumerate() {
+        let next = match step {
+            1 | -1 if i > 0 && steps[i - 1] == step => current + 1,
+            1 | -1 => 2,
+            _ => 1,
+        };
+        if next <= current {
+            sequences.push(current);
+        }
+        current = next;
+    }
+    sequences.extend(Some(current).filter(|&current| current > 0));
+    sequences
+}
+
+// the length of the longest sequence of characters increasing or decreasing by one
+fn longest_sequence(chars: &[char]) -> usize {
+    sequences(chars).into_iter().max().unwrap_or(0)
+}
+
+// the number of elements of the runs beyond the given maximum length
+fn excess(runs: Vec<usize>, max: i32) -> usize {
+    runs.into_iter().map(|run| run.saturating_sub(max.max(0) as usize)).sum()
+}
+
+/// Computes the penalties of the violations found in the password in the context, see
+/// `FullCheck`.
+pub(crate) fn penalties(pwq: &PWQuality, ctx: &CheckContext, violations: &[Error]) -> Vec<usize> {
+    if violations.is_empty() {
+        return Vec::new();
+    }
+    let chars: Vec<char> = ctx.password().chars().collect();
+    let suggestions = suggest(pwq, ctx, None);
+    let suggested = |kind: fn(&Suggestion) -> Option<usize>| suggestions.iter().filter_map(kind).next().unwrap_or(0);
+    violations.iter().map(|error| {
+        let penalty = match *error {
+            Error::MinDigits => suggested(|s| match *s { Suggestion::AddDigits(n) => Some(n), _ => None }),
+            Error::MinUppers => suggested(|s| match *s { Suggestion::AddUppercase(n) => Some(n), _ => None }),
+            Error::MinLowers => suggested(|s| match *s { Suggestion::AddLowercase(n) => Some(n), _ => None }),
+            Error::MinOthers => suggested(|s| match *s { Suggestion::AddOthers(n) => Some(n), _ => None }),
+            Error::MinLength => suggested(|s| match *s { Suggestion::AddCharacters(n) => Some(n), _ => None }),
+            Error::MinClasses => suggested(|s| match *s { Suggestion::AddClasses(n) => Some(n), _ => None }),
+            Error::MaxConsecutive => excess(runs(&chars, |a, b| a == b), pwq.get_max_repeat()),
+            Error::MaxClassRepeat => {
+                let classes: Vec<Class> = chars.iter().cloned().map(classifier(pwq)).collect();
+                excess(runs(&classes, |a, b| a == b), pwq.get_max_class_repeat())
+            },
+            Error::MaxSequence => excess(sequences(&chars), pwq.get_max_sequence()),
+            Error::KeyboardWalk => suggested(|s| match *s {
+                Suggestion::AvoidKeyboardWalk(ref walk) => Some(walk.chars().count()),
+                _ => None,
+            }).saturating_sub(pwq.get_max_keyboard_walk().max(0) as usize),
+            _ => 1,
+        };
+        penalty.max(1)
+    }).collect()
+}
+
+/// Computes the suggestions for the password in the context, rejected with the given error.
+pub(crate) fn suggest(pwq: &PWQuality, ctx: &CheckContext, error: Option<&Error>) -> Vec<Suggestion> {
+    let password = ctx.password();
+    let chars: Vec<char> = password.chars().collect();
+    let classes: Vec<Class> = chars.iter().cloned().map(classifier(pwq)).collect();
+    let mut suggestions = Vec::new();
+
+    // classes with a negative credit are required, the other ones count towards the length
+    let mut credited = 0;
+    let mut added = 0;
+    let mut missing_classes = 0;
+    let credits = [
+        (Class::Digit, pwq.get_digit_credit(), Suggestion::AddDigits as fn(usize) -> Suggestion),
+        (Class::Upper, pwq.get_uppercase_credit(), Suggestion::AddUppercase),
+        (Class::Lower, pwq.get_lowercase_credit(), Suggestion::AddLowercase),
+        (Class::Other, pwq.get_other_credit(), Suggestion::AddOthers),
+    ];
+    for &(cls, credit, suggestion) in &credits {
+        let count = classes.iter().filter(|&&c| c == cls).count();
+        if credit < 0 {
+            let required = (-credit) as usize;
+            if count < required {
+                suggestions.push(suggestion(required - count));
+                added += required - count;
+                if count == 0 {
+                    missing_classes += 1;
+                }
+            }
+        } else {
+            credited += count.min(credit as usize);
+        }
+    }
+    let needed = (pwq.get_min_length().max(0) as usize).saturating_sub(credited + added);
+    if chars.len() < needed {
+        suggestions.insert(0, Suggestion::AddCharacters(needed - chars.len()));
+    }
+    let present = credits.iter().filter(|&&(cls, _, _)| classes.contains(&cls)).count() + missing_classes;
+    let min_classes = pwq.get_min_classes().max(0) as usize;
+    if present < min_classes {
+        suggestions.push(Suggestion::AddClasses(min_classes - present));
+    }
+
+    let max_repeat = pwq.get_max_repeat();
+    if max_repeat > 0 && longest_run(&chars, |a, b| a == b) > max_repeat as usize {
+        suggestions.push(Suggestion::AvoidRepeats(max_repeat as usize));
+    }
+    let max_class_repeat = pwq.get_max_class_repeat();
+    if max_class_repeat > 0 && longest_run(&classes, |a, b| a == b) > max_class_repeat as usize {
+        suggestions.push(Suggestion::AvoidClassRepeats(max_class_repeat as usize));
+    }
+    let max_sequence = pwq.get_max_sequence();
+    if max_sequence > 0 && longest_sequence(&chars) > max_sequence as usize {
+        suggestions.push(Suggestion::AvoidSequences(max_sequence as usize));
+    }
+
+    let mut words = Vec::new();
+    if let Some(username) = ctx.username() {
+        if pwq.get_user_check() && contains_word(password, username) {
+            words.push(username.to_owned());
+        }
+    }
+    if let Some(word) = ctx.user_context().and_then(|user| user.find_forbidden_word(password)) {
+        words.push(word);
+    }
+    words.extend(pwq.gecos_word(ctx));
+    if let Ok(bad_words) = pwq.get_bad_words() {
+        words.extend(bad_words.into_iter().filter(|word| contains_word(password, word)));
+    }
+    if let Some(ref list) = *pwq.bad_word_list.borrow() {
+        words.extend(list.find(password).map(str::to_owned));
+    }
+    if let Some(ref list) = *pwq.bad_phrase_list.borrow() {
+        words.extend(list.find(password).map(str::to_owned));
+    }
+    for word in words {
+        let suggestion = Suggestion::AvoidWord(word);
+        if !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    }
+
+    let max_walk = pwq.get_max_keyboard_walk();
+    if max_walk > 0 {
+        let walk = pwq.keyboard_layouts.borrow().iter()
+            .filter_map(|layout| layout.longest_walk(password))
+            .filter(|walk| walk.len() > max_walk as usize)
+            .max_by_key(|walk| walk.len());
+        if let Some(walk) = walk {
+            suggestions.push(Suggestion::AvoidKeyboardWalk(chars[walk.start..walk.end].iter().collect()));
+        }
+    }
+
+    if let Some(ref database) = *pwq.breach.borrow() {
+        if database.occurrences(password) >= pwq.get_breach_threshold() {
+            suggestions.push(Suggestion::AvoidBreachedPassword);
+        }
+    }
+
+    let from_error = match error {
+        Some(&Error::Palindrome) => Some(Suggestion::AvoidPalindrome),
+        Some(&Error::CracklibCheck) => Some(Suggestion::AvoidDictionaryWord),
+        Some(&Error::TooSimilar) | Some(&Error::SamePassword) | Some(&Error::CaseChangesOnly)
+            | Some(&Error::Rotated) | Some(&Error::History { .. }) => Some(Suggestion::DifferFromOld),
+        Some(Error::Custom { message, .. }) => Some(Suggestion::Custom(message.clone())),
+        _ => None,
+    };
+    if let Some(suggestion) = from_error {
+        suggestions.push(suggestion);
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCALES: [&str; 4] = ["en", "fr", "de", "es"];
+
+    fn suggestions() -> Vec<Suggestion> {
+        vec![
+            Suggestion::AddCharacters(1), Suggestion::AddCharacters(3), Suggestion::AddDigits(1),
+            Suggestion::AddDigits(2), Suggestion::AddUppercase(1), Suggestion::AddUppercase(2),
+            Suggestion::AddLowercase(1), Suggestion::AddLowercase(2), Suggestion::AddOthers(1),
+            Suggestion::AddOthers(2), Suggestion::AddClasses(1), Suggestion::AddClasses(2),
+            Suggestion::AvoidRepeats(2), Suggestion::AvoidClassRepeats(3), Suggestion::AvoidSequences(3),
+            Suggestion::AvoidWord("john".to_owned()), Suggestion::AvoidKeyboardWalk("qwerty".to_owned()),
+            Suggestion::AvoidPalindrome, Suggestion::AvoidDictionaryWord, Suggestion::AvoidBreachedPassword,
+            Suggestion::DifferFromOld, Suggestion::Custom("Custom rule".to_owned()),
+        ]
+    }
+
+    fn suggest_for(pwq: &PWQuality, password: &str) -> Vec<Suggestion> {
+        suggest(pwq, &CheckContext::new(password, None, None, None), None)
+    }
+
+    #[test]
+    fn messages_are_translated() {
+        for suggestion in suggestions() {
+            let english = suggestion.message("en");
+            assert_eq!(suggestion.to_string(), english);
+            for locale in &LOCALES {
+                let message = suggestion.message(locale);
+                assert!(!message.is_empty() && message != suggestion.kind(), "{:?} in {}", suggestion, locale);
+                assert!(!message.contains("{}"), "{:?} in {}: {}", suggestion, locale, message);
+                if *locale != "en" && !matches!(suggestion, Suggestion::Custom(_)) {
+                    assert_ne!(message, english, "{:?} in {}", suggestion, locale);
+                }
+            }
+        }
+        assert_eq!(Suggestion::AddDigits(1).message("fr_FR.UTF-8"), "ajoutez un chiffre");
+        assert_eq!(Suggestion::AddDigits(2).message("de"), "fügen Sie 2 Ziffern hinzu");
+        assert_eq!(Suggestion::AvoidWord("john".to_owned()).message("es"), "evite 'john'");
+        assert_eq!(Suggestion::AddCharacters(2).message("pt"), "add 2 more characters");
+    }
+
+    #[test]
+    fn ascii_classes() {
+        assert_eq!(ascii_class('7'), Class::Digit);
+        assert_eq!(ascii_class('A'), Class::Upper);
+        assert_eq!(ascii_class('a'), Class::Lower);
+        assert_eq!(ascii_class('-'), Class::Other);
+        // the library counts the letters outside of ASCII as other characters
+        assert_eq!(ascii_class('É'), Class::Other);
+        assert_eq!(ascii_class('é'), Class::Other);
+        assert_eq!(ascii_class('٣'), Class::Other);
+    }
+
+    #[test]
+    fn missing_classes() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(6);
+        pwq.set_digit_credit(-1);
+        pwq.set_uppercase_credit(-1);
+        pwq.set_other_credit(-1);
+        let suggestions = suggest_for(&pwq, "été-abc");
+        assert!(suggestions.contains(&Suggestion::AddDigits(1)));
+        assert!(suggestions.contains(&Suggestion::AddUppercase(1)));
+        assert!(!suggestions.contains(&Suggestion::AddOthers(1)));
+        assert!(suggest_for(&pwq, "ÉÈ-abcd").contains(&Suggestion::AddUppercase(1)));
+        assert!(!suggest_for(&pwq, "Ete-abc1").iter().any(|s| matches!(s, Suggestion::AddDigits(_) | Suggestion::AddUppercase(_))));
+    }
+
+    #[test]
+    fn runs_and_sequences() {
+        let pwq = PWQuality::new();
+        pwq.set_max_repeat(2);
+        pwq.set_max_class_repeat(4);
+        pwq.set_max_sequence(3);
+        let suggestions = suggest_for(&pwq, "xaaab-abcdef");
+        assert!(suggestions.contains(&Suggestion::AvoidRepeats(2)));
+        assert!(suggestions.contains(&Suggestion::AvoidClassRepeats(4)));
+        assert!(suggestions.contains(&Suggestion::AvoidSequences(3)));
+        assert_eq!(longest_run(&['a', 'b', 'b', 'b', 'c'], |a, b| a == b), 3);
+        assert_eq!(longest_sequence(&['x', '4', '3', '2', '1', 'y']), 4);
+    }
+
+    #[test]
+    fn penalties_of_runs() {
+        let pwq = PWQuality::new();
+        pwq.set_max_repeat(2);
+        pwq.set_max_class_repeat(4);
+        pwq.set_max_sequence(3);
+        let ctx = CheckContext::new("xaaaab-bbbc-9876543", None, None, None);
+        let violations = [Error::MaxConsecutive, Error::MaxClassRepeat, Error::MaxSequence, Error::Palindrome];
+        assert_eq!(penalties(&pwq, &ctx, &violations), vec![3, 5, 4, 1]);
+        assert!(penalties(&pwq, &ctx, &[]).is_empty());
+        assert_eq!(runs(&['a', 'a', 'b', 'c', 'c', 'c'], |a, b| a == b), vec![2, 1, 3]);
+        assert_eq!(sequences(&['a', 'b', 'c', 'b', 'x']), vec![3, 2, 1]);
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn unicode_classes() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(6);
+        pwq.set_uppercase_credit(-1);
+        pwq.set_other_credit(-1);
+        pwq.set_unicode_options(Some(unicode::UnicodeOptions::default()));
+        let suggestions = suggest_for(&pwq, "Éte-abc");
+        assert!(!suggestions.contains(&Suggestion::AddUppercase(1)));
+        assert!(suggest_for(&pwq, "Éteéabc").contains(&Suggestion::AddOthers(1)));
+    }
+
+}
@@ -0,0 +1,19 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Deterministic randomness for tests, available with the `test-util` feature.
+//!
+//! The generators of `PWQuality` accepting a random number generator produce the same
+//! passwords when given generators created with the same seed, which makes snapshot tests
+//! of generated credentials stable. These generators must not be used outside of tests.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+
+/// A cryptographically secure generator whose output is entirely determined by its seed.
+pub type DeterministicRng = ChaCha20Rng;
+
+/// Creates a deterministic generator from the given seed.
+pub fn seeded_rng(seed: u64) -> DeterministicRng {
+    ChaCha20Rng::seed_from_u64(seed)
+}
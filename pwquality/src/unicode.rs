@@ -0,0 +1,406 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Unicode aware checks.
+//!
+//! The library works on bytes: every byte that is not an ASCII digit or letter counts as
+//! an "other" character, so a single accented letter or a letter of a non-Latin script
+//! counts several times towards the credit of other characters, and towards the length.
+//! The options set with `PWQuality::set_unicode_options` change the checks as follows:
+//!
+//!  - the password, the old password and the username are normalized to NFKC first,
+//!  - the classes of characters are derived from the Unicode properties of the characters,
+//!    letters of scripts without case counting as lowercase letters,
+//!  - the length of the password is counted in bytes or in grapheme clusters,
+//!  - the password is also checked against the dictionary, the username and the forbidden
+//!    words after folding the characters confusable with ASCII letters (e.g. the Cyrillic `а`).
+//!
+//! When the classes are script aware or the length is counted in graphemes, the length and
+//! class checks of the library are replaced by the ones of this module, with the same rules.
+
+use report::FullCheck;
+use rule::CheckContext;
+use PWQuality;
+
+use pwquality_sys::Error;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_normalization::char::is_combining_mark;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How the length of a password is counted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum LengthMode {
+    /// The length is the number of bytes of the UTF-8 encoding, as done by the library.
+    Bytes,
+    /// The length is the number of extended grapheme clusters, i.e. of user perceived characters.
+    Graphemes,
+}
+
+/// The Unicode handling of an instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct UnicodeOptions {
+    /// Whether the passwords and usernames are normalized to NFKC before being checked.
+    pub normalize: bool,
+    /// Whether the classes of characters are derived from their Unicode properties.
+    pub script_classes: bool,
+    /// Whether the characters confusable with ASCII letters are folded for the dictionary,
+    /// username and forbidden words checks.
+    pub fold_confusables: bool,
+    /// How the length of the password is counted.
+    pub length: LengthMode,
+}
+
+impl Default for UnicodeOptions {
+    fn default() -> Self {
+        UnicodeOptions {
+            normalize: true,
+            script_classes: true,
+            fold_confusables: true,
+            length: LengthMode::Graphemes,
+        }
+    }
+}
+
+/// A class of characters, as counted by the class and credit settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    Digit,
+    Uppercase,
+    Lowercase,
+    Other,
+}
+
+/// Returns the class of a character from its Unicode properties.
+/// Letters of scripts without case (e.g. CJK, Arabic) are lowercase letters.
+pub fn classify(c: char) -> CharClass {
+    if c.is_numeric() {
+        CharClass::Digit
+    } else if c.is_uppercase() {
+        CharClass::Uppercase
+    } else if c.is_alphabetic() {
+        CharClass::Lowercase
+    } else {
+        CharClass::Other
+    }
+}
+
+/// Returns the NFKC normalization of the text.
+pub fn normalize(text: &str) -> String {
+    text.nfkc().collect()
+}
+
+/// Returns the length of the text, counted according to the mode.
+pub fn length(text: &str, mode: LengthMode) -> usize {
+    match mode {
+        LengthMode::Bytes => text.len(),
+        LengthMode::Graphemes => text.graphemes(true).count(),
+    }
+}
+
+// Cyrillic and Greek letters looking like ASCII letters
+const CONFUSABLES: &[(char, char)] = &[
+    ('а', 'a'), ('в', 'b'), ('е', 'e'), ('к', 'k'), ('м', 'm'), ('н', 'h'), ('о', 'o'), ('р', 'p'),
+    ('с', 'c'), ('т', 't'), ('у', 'y'), ('х', 'x'), ('ѕ', 's'), ('і', 'i'), ('ј', 'j'), ('ԁ', 'd'),
+    ('ԛ', 'q'), ('ԝ', 'w'), ('А', 'A'), ('В', 'B'), ('Е', 'E'), ('К', 'K'), ('М', 'M'), ('Н', 'H'),
+    ('О', 'O'), ('Р', 'P'), ('С', 'C'), ('Т', 'T'), ('Х', 'X'), ('Ѕ', 'S'), ('І', 'I'), ('Ј', 'J'),
+    ('Ү', 'Y'), ('α', 'a'), ('ι', 'i'), ('κ', 'k'), ('ν', 'v'), ('ο', 'o'), ('ρ', 'p'), ('υ', 'u'),
+    ('χ', 'x'), ('Α', 'A'), ('Β', 'B'), ('Ε', 'E'), ('Ζ', 'Z'), ('Η', 'H'), ('Ι', 'I'), ('Κ', 'K'),
+    ('Μ', 'M'), ('Ν', 'N'), ('Ο', 'O'), ('Ρ', 'P'), ('Τ', 'T'), ('Υ', 'Y'), ('Χ', 'X'), ('ı', 'i'),
+];
+
+/// Folds the characters of the text confusable with ASCII letters to these letters,
+/// and removes the diacritics (e.g. `pаsswörd` with a Cyrillic `а` becomes `password`).
+pub fn fold_confusables(text: &str) -> String {
+    text.nfkd()
+        .filter(|&c| !is_combining_mark(c))
+        .map(|c| CONFUSABLES.iter().find(|&&(from, _)| from == c).map(|&(_, to)| to).unwrap_or(c))
+        .collect()
+}
+
+// the length and class checks of the library, with Unicode classes and length, reporting all
+// the failed checks
+fn check_length_and_classes(pwq: &PWQuality, password: &str, options: &UnicodeOptions, violations: &mut Vec<Error>) {
+    let classify = |c: char| if options.script_classes {
+        classify(c)
+    } else if c.is_ascii_digit() {
+        CharClass::Digit
+    } else if c.is_ascii_uppercase() {
+        CharClass::Uppercase
+    } else if c.is_ascii_lowercase() {
+        CharClass::Lowercase
+    } else {
+        CharClass::Other
+    };
+    let classes: Vec<CharClass> = password.chars().map(classify).collect();
+    let count = |class: CharClass| classes.iter().filter(|&&c| c == class).count() as i32;
+
+    let mut size = pwq.get_min_length();
+    let credits = [
+        (CharClass::Digit, pwq.get_digit_credit(), Error::MinDigits),
+        (CharClass::Uppercase, pwq.get_uppercase_credit(), Error::MinUppers),
+        (CharClass::Lowercase, pwq.get_lowercase_credit(), Error::MinLowers),
+        (CharClass::Other, pwq.get_other_credit(), Error::MinOthers),
+    ];
+    for &(class, credit, ref error) in &credits {
+        let count = count(class);
+        if credit >= 0 {
+            size -= count.min(credit);
+        } else if count < -credit {
+            violations.push(error.clone());
+        }
+    }
+    let present = credits.iter().filter(|&&(class, _, _)| count(class) > 0).count() as i32;
+    if present < pwq.get_min_classes() {
+        violations.push(Error::MinClasses);
+    }
+    let max_class_repeat = pwq.get_max_class_repeat();
+    if max_class_repeat > 0 {
+        let mut run = 0;
+        for (i, class) in classes.iter().enumerate() {
+            run = if i > 0 && classes[i - 1] == *class { run + 1 } else { 1 };
+            if run > max_class_repeat {
+                violations.push(Error::MaxClassRepeat);
+                break;
+            }
+        }
+    }
+    if (length(password, options.length) as i32) < size {
+        violations.push(Error::MinLength);
+    }
+}
+
+// the library checks, the length and class checks being replaced by the ones of this module
+// when they are native; the score is missing when a check cannot be disabled
+fn check_library_all(pwq: &PWQuality, ctx: &CheckContext, options: &UnicodeOptions, violations: &mut Vec<Error>) -> Option<i32> {
+    if options.script_classes || options.length == LengthMode::Graphemes {
+        check_length_and_classes(pwq, ctx.password(), options, violations);
+        let relaxed = pwq.library_copy();
+        relaxed.set_min_length(0);
+        relaxed.set_digit_credit(0);
+        relaxed.set_uppercase_credit(0);
+        relaxed.set_lowercase_credit(0);
+        relaxed.set_other_credit(0);
+        relaxed.set_min_classes(0);
+        relaxed.set_max_class_repeat(0);
+        relaxed.check_library_relaxing(ctx.password(), ctx.old_password(), ctx.username(), violations)
+    } else {
+        pwq.check_library_all(ctx.password(), ctx.old_password(), ctx.username(), violations)
+    }
+}
+
+// the checks looking for words, on the password with the confusable characters folded
+fn check_confusables(pwq: &PWQuality, ctx: &CheckContext, options: &UnicodeOptions) -> Result<(), Error> {
+    if !options.fold_confusables {
+        return Ok(());
+    }
+    let folded = fold_confusables(ctx.password());
+    if folded == ctx.password() {
+        return Ok(());
+    }
+    match pwq.library_copy().check_library(&folded, None, ctx.username()) {
+        Err(error @ Error::CracklibCheck) | Err(error @ Error::UserCheck)
+            | Err(error @ Error::GecosCheck) | Err(error @ Error::BadWords { .. }) => return Err(error),
+        _ => (),
+    }
+    if let Some(user) = ctx.user_context() {
+        if user.find_forbidden_word(&folded).is_some() {
+            return Err(Error::GecosCheck);
+        }
+    }
+    Ok(())
+}
+
+// the owned normalized texts of a context
+struct Normalized {
+    password: String,
+    old_password: Option<String>,
+    username: Option<String>,
+}
+
+impl Normalized {
+    fn new(ctx: &CheckContext, options: &UnicodeOptions) -> Self {
+        let normalized = |text: &str| if options.normalize { normalize(text) } else { text.to_owned() };
+        Normalized {
+            password: normalized(ctx.password()),
+            old_password: ctx.old_password().map(normalized),
+            username: ctx.username().map(normalized),
+        }
+    }
+}
+
+/// Runs the checks of the instance on the password in the context, with the Unicode handling
+/// given in the options.
+pub(crate) fn check(pwq: &PWQuality, ctx: CheckContext, options: &UnicodeOptions) -> Result<i32, Error> {
+    let normalized = Normalized::new(&ctx, options);
+    let ctx = CheckContext::new(&normalized.password, normalized.old_password.as_deref(),
+                                normalized.username.as_deref(), ctx.user_context());
+
+    let mut violations = Vec::new();
+    let score = check_library_all(pwq, &ctx, options, &mut violations);
+    if let Some(error) = violations.into_iter().next() {
+        return Err(error);
+    }
+    check_confusables(pwq, &ctx, options)?;
+    pwq.run_additional_checks(ctx, score.unwrap_or(0))
+}
+
+/// Runs all the checks of the instance on the password in the context, with the Unicode
+/// handling given in the options, reporting all the failed ones.
+pub(crate) fn check_all(pwq: &PWQuality, ctx: CheckContext, options: &UnicodeOptions) -> FullCheck {
+    let normalized = Normalized::new(&ctx, options);
+    let ctx = CheckContext::new(&normalized.password, normalized.old_password.as_deref(),
+                                normalized.username.as_deref(), ctx.user_context());
+
+    let mut violations = Vec::new();
+    let score = check_library_all(pwq, &ctx, options, &mut violations);
+    if let Err(error) = check_confusables(pwq, &ctx, options) {
+        if !violations.contains(&error) {
+            violations.push(error);
+        }
+    }
+    pwq.run_all_additional_checks(ctx, score, violations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn native() -> UnicodeOptions {
+        UnicodeOptions::default()
+    }
+
+    fn bytes() -> UnicodeOptions {
+        UnicodeOptions {
+            normalize: false,
+            script_classes: false,
+            fold_confusables: false,
+            length: LengthMode::Bytes,
+        }
+    }
+
+    fn violations(pwq: &PWQuality, password: &str, options: &UnicodeOptions) -> Vec<Error> {
+        let mut violations = Vec::new();
+        check_length_and_classes(pwq, password, options, &mut violations);
+        violations
+    }
+
+    #[test]
+    fn normalization() {
+        assert_eq!(normalize("ﬁne"), "fine");
+        assert_eq!(normalize("Ｐａｓｓ１"), "Pass1");
+        assert_eq!(normalize("e\u{301}"), "é");
+        assert_eq!(normalize("password"), "password");
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(classify('7'), CharClass::Digit);
+        assert_eq!(classify('٣'), CharClass::Digit);
+        assert_eq!(classify('Ⅻ'), CharClass::Digit);
+        assert_eq!(classify('A'), CharClass::Uppercase);
+        assert_eq!(classify('Ж'), CharClass::Uppercase);
+        assert_eq!(classify('ж'), CharClass::Lowercase);
+        assert_eq!(classify('é'), CharClass::Lowercase);
+        // scripts without case
+        assert_eq!(classify('漢'), CharClass::Lowercase);
+        assert_eq!(classify('ب'), CharClass::Lowercase);
+        assert_eq!(classify('!'), CharClass::Other);
+        assert_eq!(classify('€'), CharClass::Other);
+        assert_eq!(classify(' '), CharClass::Other);
+    }
+
+    #[test]
+    fn confusables() {
+        assert_eq!(fold_confusables("pаsswörd"), "password");
+        assert_eq!(fold_confusables("Раураl"), "Paypal");
+        assert_eq!(fold_confusables("ΑΒΕ"), "ABE");
+        assert_eq!(fold_confusables("Wq-7-Zt!x"), "Wq-7-Zt!x");
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(length("password", LengthMode::Bytes), 8);
+        assert_eq!(length("password", LengthMode::Graphemes), 8);
+        assert_eq!(length("é", LengthMode::Bytes), 2);
+        assert_eq!(length("é", LengthMode::Graphemes), 1);
+        assert_eq!(length("e\u{301}", LengthMode::Bytes), 3);
+        assert_eq!(length("e\u{301}", LengthMode::Graphemes), 1);
+        assert_eq!(length("🇫🇷", LengthMode::Bytes), 8);
+        assert_eq!(length("🇫🇷", LengthMode::Graphemes), 1);
+        assert_eq!(length("漢字", LengthMode::Graphemes), 2);
+    }
+
+    #[test]
+    fn library_rules_on_ascii() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(10);
+        pwq.set_digit_credit(-1);
+        pwq.set_uppercase_credit(1);
+        pwq.set_lowercase_credit(0);
+        pwq.set_other_credit(2);
+        pwq.set_min_classes(3);
+        let passwords = ["abcdefghij", "abcdefgh1", "Abcdefg1", "Ab1!!ab", "Ab1!!abc", "ABCDEFGHIJK", "a1-b2-c3-d4"];
+        for password in passwords.iter() {
+            let mut expected = Vec::new();
+            pwq.check_length_and_classes(password, &mut expected);
+            let found = violations(&pwq, password, &bytes());
+            assert!(found.iter().all(|error| expected.contains(error)), "{}: {:?} {:?}", password, found, expected);
+            assert!(expected.iter().all(|error| found.contains(error)), "{}: {:?} {:?}", password, found, expected);
+            assert_eq!(found.is_empty(), pwq.library_copy().check_library(password, None, None).is_ok(), "{}", password);
+        }
+    }
+
+    #[test]
+    fn native_length_and_classes() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(8);
+        pwq.set_digit_credit(0);
+        pwq.set_uppercase_credit(0);
+        pwq.set_lowercase_credit(0);
+        pwq.set_other_credit(0);
+        // counted in bytes, each CJK character is 3 characters of another class
+        assert!(violations(&pwq, "漢字漢字", &bytes()).is_empty());
+        assert_eq!(violations(&pwq, "漢字漢字", &native()), vec![Error::MinLength]);
+        assert!(violations(&pwq, "漢字漢字漢字漢字", &native()).is_empty());
+        pwq.set_min_classes(2);
+        assert_eq!(violations(&pwq, "漢字漢字漢字漢字", &native()), vec![Error::MinClasses]);
+        assert!(violations(&pwq, "漢字漢字漢字漢字1", &native()).is_empty());
+        pwq.set_max_class_repeat(3);
+        assert_eq!(violations(&pwq, "漢字漢字漢字漢字1", &native()), vec![Error::MaxClassRepeat]);
+        assert!(violations(&pwq, "漢字漢1字漢字2漢字", &native()).is_empty());
+    }
+
+    #[test]
+    fn check_and_check_all_agree() {
+        let pwq = PWQuality::new();
+        pwq.set_min_length(10);
+        pwq.set_digit_credit(-1);
+        pwq.set_uppercase_credit(0);
+        pwq.set_lowercase_credit(0);
+        pwq.set_other_credit(0);
+        let passwords = [
+            "漢字漢字漢字漢字漢字1", "漢字漢字1", "Pässwörd-2024", "Pässwörd-", "ﬁﬁﬁﬁﬁ-2024",
+            "🇫🇷🇫🇷🇫🇷🇫🇷🇫🇷7", "xpаsswоrd-9!", "Wq-7-Zt!x-Vb4",
+        ];
+        for options in [native(), bytes()].iter() {
+            pwq.set_unicode_options(Some(*options));
+            for password in passwords.iter() {
+                let username = Some("password".to_owned());
+                let result = pwq.check(password.to_string(), None, username.clone());
+                let full = pwq.check_all(password.to_string(), None, username);
+                assert_eq!(result.as_ref().err(), full.violations().first(), "{} {:?}", password, options);
+            }
+        }
+    }
+
+    #[test]
+    fn folded_confusables() {
+        let pwq = PWQuality::new();
+        pwq.set_unicode_options(Some(native()));
+        let username = Some("password".to_owned());
+        assert_eq!(pwq.check("xpаsswоrd-9!Q".to_owned(), None, username.clone()), Err(Error::UserCheck));
+        pwq.set_unicode_options(Some(UnicodeOptions { fold_confusables: false, ..native() }));
+        assert!(pwq.check("xpаsswоrd-9!Q".to_owned(), None, username).is_ok());
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (c) 2017, Lucas Satabin
+// Licensed under the MIT license, see the LICENSE file or <http://opensource.org/licenses/MIT>
+
+//! Lookup of users and groups in the system databases.
+
+use libc::{self, c_char, c_int, gid_t};
+
+use std::ffi::{CStr, CString};
+use std::fs;
+use std::io;
+use std::mem;
+use std::ptr;
+
+// the local password file, as read by `local_users_only` in pam_pwquality
+const PASSWD_FILE: &str = "/etc/passwd";
+
+/// An entry of the password database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Passwd {
+    pub name: String,
+    pub gid: gid_t,
+    pub gecos: String,
+}
+
+fn string(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        unsafe { CStr::from_ptr(ptr).to_string_lossy().into_owned() }
+    }
+}
+
+// calls a reentrant lookup function, growing the buffer while it is too small
+fn with_buffer<F: FnMut(&mut [c_char]) -> c_int>(mut lookup: F) -> io::Result<()> {
+    let mut buffer: Vec<c_char> = vec![0; 1024];
+    loop {
+        match lookup(&mut buffer) {
+            0 => return Ok(()),
+            libc::ERANGE if buffer.len() < 1 << 20 => {
+                let len = buffer.len() * 2;
+                buffer.resize(len, 0);
+            },
+            errno => return Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+}
+
+/// Looks the user with the given name up with `getpwnam_r`.
+pub(crate) fn getpwnam(name: &str) -> io::Result<Option<Passwd>> {
+    let c_name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut entry = None;
+    with_buffer(|buffer| unsafe {
+        let mut pwd: libc::passwd = mem::zeroed();
+        let mut result = ptr::null_mut();
+        let res = libc::getpwnam_r(c_name.as_ptr(), &mut pwd, buffer.as_mut_ptr(), buffer.len(), &mut result);
+        if res == 0 && !result.is_null() {
+            entry = Some(Passwd {
+                name: string(pwd.pw_name),
+                gid: pwd.pw_gid,
+                gecos: string(pwd.pw_gecos),
+            });
+        }
+        res
+    })?;
+    Ok(entry)
+}
+
+/// Returns the names of the groups the user with the given name and primary group belongs to.
+pub(crate) fn group_names(name: &str, gid: gid_t) -> io::Result<Vec<String>> {
+    let c_name = CString::new(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut gids: Vec<gid_t> = vec![0; 32];
+    loop {
+        let mut count = gids.len() as c_int;
+        let res = unsafe { libc::getgrouplist(c_name.as_ptr(), gid, gids.as_mut_ptr(), &mut count) };
+        if res >= 0 {
+            gids.truncate(count.max(0) as usize);
+            break;
+        }
+        // the list is too small, `count` holds the needed size
+        let len = (count as usize).max(gids.len() * 2);
+        gids.resize(len, 0);
+    }
+    let mut names = Vec::with_capacity(gids.len());
+    for gid in gids {
+        let mut group_name = None;
+        with_buffer(|buffer| unsafe {
+            let mut grp: libc::group = mem::zeroed();
+            let mut result = ptr::null_mut();
+            let res = libc::getgrgid_r(gid, &mut grp, buffer.as_mut_ptr(), buffer.len(), &mut result);
+            if res == 0 && !result.is_null() {
+                group_name = Some(string(grp.gr_name));
+            }
+            res
+        })?;
+        if let Some(group_name) = group_name {
+            if !names.contains(&group_name) {
+                names.push(group_name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+/// Returns whether the user with the given name is listed in the local password file,
+/// regardless of the other sources of the system databases (e.g. LDAP).
+/// The user is considered as not local if the file cannot be read.
+pub(crate) fn is_local(name: &str) -> bool {
+    match fs::read_to_string(PASSWD_FILE) {
+        Ok(content) => content.lines().any(|line| line.split(':').next() == Some(name)),
+        Err(_) => false,
+    }
+}